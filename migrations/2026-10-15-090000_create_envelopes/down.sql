@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE period_locks;
+DROP TABLE envelopes;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE envelopes (
+    id SERIAL PRIMARY KEY,
+    ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+    month DATE NOT NULL,
+    assigned DOUBLE PRECISION NOT NULL DEFAULT 0,
+    carried_in DOUBLE PRECISION NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (ledger_id, month)
+);
+
+CREATE TABLE period_locks (
+    month DATE PRIMARY KEY,
+    locked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP FUNCTION ledger_kind_at(INTEGER, TIMESTAMP);
+DROP TABLE ledger_kinds;
@@ -0,0 +1,22 @@
+-- Your SQL goes here
+CREATE TABLE ledger_kinds (
+    id SERIAL PRIMARY KEY,
+    ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+    kind VARCHAR(20) NOT NULL,
+    effective_from DATE NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (ledger_id, effective_from)
+);
+
+-- Kind of a ledger at a point in time: the latest history entry effective
+-- by then, falling back to ledgers.kind for ledgers that were never rekinded
+CREATE OR REPLACE FUNCTION ledger_kind_at(p_ledger_id INTEGER, p_at TIMESTAMP)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE(
+        (SELECT k.kind FROM ledger_kinds k
+         WHERE k.ledger_id = p_ledger_id AND k.effective_from <= p_at
+         ORDER BY k.effective_from DESC
+         LIMIT 1),
+        (SELECT l.kind FROM ledgers l WHERE l.id = p_ledger_id)
+    )
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_proceedings_db_to;
+DROP INDEX idx_proceedings_cr_from;
+DROP INDEX idx_proceedings_created_at;
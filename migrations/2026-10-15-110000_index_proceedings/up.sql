@@ -0,0 +1,4 @@
+-- Your SQL goes here
+CREATE INDEX idx_proceedings_created_at ON proceedings (created_at);
+CREATE INDEX idx_proceedings_cr_from ON proceedings (cr_from);
+CREATE INDEX idx_proceedings_db_to ON proceedings (db_to);
@@ -0,0 +1,11 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_proceedings_effective_date;
+DROP TRIGGER proceedings_effective_date ON proceedings;
+DROP FUNCTION proceedings_effective_date();
+
+ALTER TABLE proceedings
+    DROP CONSTRAINT proceedings_uuid_key,
+    DROP COLUMN effective_date,
+    DROP COLUMN uuid,
+    DROP COLUMN currency,
+    ALTER COLUMN amount TYPE DOUBLE PRECISION;
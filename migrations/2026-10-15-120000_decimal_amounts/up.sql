@@ -0,0 +1,29 @@
+-- Your SQL goes here
+ALTER TABLE proceedings
+    ALTER COLUMN amount TYPE NUMERIC(14, 2) USING ROUND(amount::numeric, 2),
+    ADD COLUMN currency CHAR(3) NOT NULL DEFAULT 'INR',
+    ADD COLUMN uuid UUID,
+    ADD COLUMN effective_date DATE;
+
+UPDATE proceedings
+SET uuid = gen_random_uuid(),
+    effective_date = COALESCE(created_at::date, CURRENT_DATE);
+
+ALTER TABLE proceedings
+    ALTER COLUMN uuid SET DEFAULT gen_random_uuid(),
+    ALTER COLUMN uuid SET NOT NULL,
+    ALTER COLUMN effective_date SET NOT NULL,
+    ADD CONSTRAINT proceedings_uuid_key UNIQUE (uuid);
+
+CREATE FUNCTION proceedings_effective_date() RETURNS TRIGGER AS $$
+BEGIN
+    NEW.effective_date := COALESCE(NEW.effective_date, NEW.created_at::date, CURRENT_DATE);
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER proceedings_effective_date
+    BEFORE INSERT ON proceedings
+    FOR EACH ROW EXECUTE FUNCTION proceedings_effective_date();
+
+CREATE INDEX idx_proceedings_effective_date ON proceedings (effective_date);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE snapshot_items;
+DROP TABLE snapshots;
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+CREATE TABLE snapshots (
+    id SERIAL PRIMARY KEY,
+    ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+    balance NUMERIC(14, 2) NOT NULL,
+    taken_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+);
+
+CREATE TABLE snapshot_items (
+    snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+    proceeding_id INTEGER NOT NULL,
+    amount NUMERIC(14, 2) NOT NULL,
+    PRIMARY KEY (snapshot_id, proceeding_id)
+);
+
+CREATE INDEX idx_snapshots_ledger_id ON snapshots (ledger_id, taken_at);
@@ -0,0 +1,9 @@
+-- Your SQL goes here
+CREATE TABLE goals (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(30) NOT NULL UNIQUE,
+    target NUMERIC(14, 2) NOT NULL,
+    target_date DATE,
+    ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP INDEX idx_proceedings_pending;
+ALTER TABLE proceedings DROP COLUMN clears_on;
+ALTER TABLE proceedings DROP COLUMN pending;
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE proceedings ADD COLUMN pending BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE proceedings ADD COLUMN clears_on DATE;
+CREATE INDEX idx_proceedings_pending ON proceedings (clears_on) WHERE pending;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE ledger_policies;
+ALTER TABLE proceedings DROP COLUMN tags;
+ALTER TABLE proceedings DROP COLUMN project;
+ALTER TABLE proceedings DROP COLUMN payee;
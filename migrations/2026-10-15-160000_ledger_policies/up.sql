@@ -0,0 +1,13 @@
+-- Your SQL goes here
+ALTER TABLE proceedings ADD COLUMN payee TEXT;
+ALTER TABLE proceedings ADD COLUMN project TEXT;
+ALTER TABLE proceedings ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}';
+
+CREATE TABLE ledger_policies (
+    ledger_id INTEGER PRIMARY KEY REFERENCES ledgers(id),
+    require_payee BOOLEAN NOT NULL DEFAULT false,
+    require_project BOOLEAN NOT NULL DEFAULT false,
+    required_tag VARCHAR(30),
+    default_narration TEXT,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE invoice_payments;
+DROP TABLE invoices;
@@ -0,0 +1,19 @@
+-- Your SQL goes here
+CREATE TABLE invoices (
+    id SERIAL PRIMARY KEY,
+    ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+    amount NUMERIC(14, 2) NOT NULL,
+    issued_on DATE NOT NULL DEFAULT CURRENT_DATE,
+    due_on DATE NOT NULL,
+    note TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE invoice_payments (
+    invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+    proceeding_id INTEGER NOT NULL REFERENCES proceedings(id),
+    amount NUMERIC(14, 2) NOT NULL,
+    PRIMARY KEY (invoice_id, proceeding_id)
+);
+
+CREATE INDEX idx_invoices_ledger_id ON invoices (ledger_id);
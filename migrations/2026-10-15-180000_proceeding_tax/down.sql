@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE proceedings DROP COLUMN tax_rate;
+ALTER TABLE proceedings DROP COLUMN tax_amount;
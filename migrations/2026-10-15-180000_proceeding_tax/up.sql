@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE proceedings ADD COLUMN tax_amount NUMERIC(14, 2);
+ALTER TABLE proceedings ADD COLUMN tax_rate NUMERIC(5, 2);
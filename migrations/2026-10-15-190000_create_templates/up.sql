@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE templates (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(30) NOT NULL UNIQUE,
+    patron_id INTEGER NOT NULL REFERENCES ledgers(id),
+    outlay_id INTEGER NOT NULL REFERENCES ledgers(id),
+    amount NUMERIC(14, 2) NOT NULL,
+    narration TEXT,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,7 @@
+-- Your SQL goes here
+CREATE TABLE fx_rates (
+    currency CHAR(3) NOT NULL,
+    rate_on DATE NOT NULL,
+    rate NUMERIC(14, 6) NOT NULL CHECK (rate > 0),
+    PRIMARY KEY (currency, rate_on)
+);
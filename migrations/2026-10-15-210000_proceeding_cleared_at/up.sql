@@ -0,0 +1,2 @@
+-- Your SQL goes here
+ALTER TABLE proceedings ADD COLUMN cleared_at TIMESTAMP;
@@ -0,0 +1,18 @@
+-- This file should undo anything in `up.sql`
+DROP FUNCTION ledger_kind_at(INTEGER, TIMESTAMPTZ);
+
+ALTER TABLE proceedings
+    ALTER COLUMN created_at TYPE TIMESTAMP,
+    ALTER COLUMN updated_at TYPE TIMESTAMP,
+    ALTER COLUMN cleared_at TYPE TIMESTAMP;
+
+CREATE OR REPLACE FUNCTION ledger_kind_at(p_ledger_id INTEGER, p_at TIMESTAMP)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE(
+        (SELECT k.kind FROM ledger_kinds k
+         WHERE k.ledger_id = p_ledger_id AND k.effective_from <= p_at
+         ORDER BY k.effective_from DESC
+         LIMIT 1),
+        (SELECT l.kind FROM ledgers l WHERE l.id = p_ledger_id)
+    )
+$$ LANGUAGE SQL STABLE;
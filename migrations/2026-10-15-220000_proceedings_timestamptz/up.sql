@@ -0,0 +1,19 @@
+-- Your SQL goes here
+-- Existing values are read as wall-clock times in the session's time zone
+DROP FUNCTION ledger_kind_at(INTEGER, TIMESTAMP);
+
+ALTER TABLE proceedings
+    ALTER COLUMN created_at TYPE TIMESTAMPTZ,
+    ALTER COLUMN updated_at TYPE TIMESTAMPTZ,
+    ALTER COLUMN cleared_at TYPE TIMESTAMPTZ;
+
+CREATE OR REPLACE FUNCTION ledger_kind_at(p_ledger_id INTEGER, p_at TIMESTAMPTZ)
+RETURNS VARCHAR AS $$
+    SELECT COALESCE(
+        (SELECT k.kind FROM ledger_kinds k
+         WHERE k.ledger_id = p_ledger_id AND k.effective_from <= p_at
+         ORDER BY k.effective_from DESC
+         LIMIT 1),
+        (SELECT l.kind FROM ledgers l WHERE l.id = p_ledger_id)
+    )
+$$ LANGUAGE SQL STABLE;
@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE links (
+    id SERIAL PRIMARY KEY,
+    from_id INTEGER NOT NULL REFERENCES proceedings(id) ON DELETE CASCADE,
+    to_id INTEGER NOT NULL REFERENCES proceedings(id) ON DELETE CASCADE,
+    kind VARCHAR(20) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (from_id, to_id),
+    CHECK (from_id <> to_id)
+);
+
+CREATE INDEX idx_links_to_id ON links (to_id);
@@ -0,0 +1,318 @@
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime};
+use dialoguer::{theme::ColorfulTheme, Input, Select};
+
+use crate::ledger_kinds::KIND_SPANS;
+use crate::{dates, output, SpendEntry, WalletDB, WalletError};
+
+// An expense ledger's envelope for one month
+pub(crate) struct Envelope {
+    ledger_id: i32,
+    pub code: String,
+    pub assigned: f64,
+    carried_in: f64,
+    pub spent: f64,
+}
+
+impl Envelope {
+    pub fn available(&self) -> f64 {
+        self.carried_in + self.assigned - self.spent
+    }
+}
+
+// First day of the month named by `month` ("apr", "2025-04", ...) or of the
+// current month when omitted.
+pub fn month_start(month: Option<&str>) -> Result<NaiveDate, WalletError> {
+    let today = dates::today();
+    let day = match month {
+        Some(month) => dates::parse_span(month, today)?.0,
+        None => today,
+    };
+    Ok(day.with_day(1).unwrap())
+}
+
+pub(crate) fn month_range(month: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+    let next = month.checked_add_months(Months::new(1)).unwrap();
+    (
+        month.and_hms_opt(0, 0, 0).unwrap(),
+        next.and_hms_opt(0, 0, 0).unwrap(),
+    )
+}
+
+impl WalletDB {
+    // Rejects writes dated inside a month that has been closed
+    pub(crate) fn ensure_unlocked(&mut self, day: NaiveDate) -> Result<(), WalletError> {
+        let month = day.with_day(1).unwrap();
+        let locked = self.client.query_opt(
+            "SELECT locked_at FROM period_locks WHERE month = $1",
+            &[&month],
+        )?;
+        if locked.is_some() {
+            return Err(WalletError::PeriodLocked(format!(
+                "{} is closed; entries dated {} are not allowed",
+                month.format("%B %Y"),
+                day
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn load_envelopes(
+        &mut self,
+        month: NaiveDate,
+    ) -> Result<Vec<Envelope>, WalletError> {
+        let (start, end) = month_range(month);
+        let rows = self.client.query(
+            "
+            SELECT
+                l.id,
+                l.code,
+                COALESCE(e.assigned, 0),
+                COALESCE(e.carried_in, 0),
+                (COALESCE((
+                    SELECT SUM(p.amount)
+                    FROM proceedings p
+                    WHERE p.db_to = l.id AND p.created_at >= $2::timestamp AND p.created_at < $3::timestamp
+                        AND NOT p.pending
+                ), 0) - COALESCE((
+                    SELECT SUM(p.amount)
+                    FROM proceedings p
+                    WHERE p.cr_from = l.id AND p.created_at >= $2::timestamp AND p.created_at < $3::timestamp
+                        AND NOT p.pending
+                ), 0))::float8 as spent
+            FROM ledgers l
+            LEFT JOIN envelopes e ON e.ledger_id = l.id AND e.month = $1
+            WHERE l.kind = 'EXPENSE'
+            ORDER BY l.code
+            ",
+            &[&month, &start, &end],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Envelope {
+                ledger_id: row.get(0),
+                code: row.get(1),
+                assigned: row.get(2),
+                carried_in: row.get(3),
+                spent: row.get(4),
+            })
+            .collect())
+    }
+
+    pub(crate) fn month_income(&mut self, month: NaiveDate) -> Result<f64, WalletError> {
+        let (start, end) = month_range(month);
+        let row = self.client.query_one(
+            &format!(
+                "
+                WITH {}
+                SELECT COALESCE(SUM(p.amount), 0)::float8
+                FROM proceedings p
+                JOIN ledgers l ON l.id = p.cr_from
+                LEFT JOIN kind_spans k ON k.ledger_id = l.id
+                    AND k.effective_from <= p.created_at
+                    AND (k.effective_to IS NULL OR p.created_at < k.effective_to)
+                WHERE COALESCE(k.kind, l.kind) = 'INCOME'
+                    AND p.created_at >= $1::timestamp AND p.created_at < $2::timestamp
+                    AND NOT p.pending
+                ",
+                KIND_SPANS
+            ),
+            &[&start, &end],
+        )?;
+        Ok(row.get(0))
+    }
+
+    // Zero-based month close: assign every unbudgeted rupee of the month's
+    // income to envelopes, carry envelope balances into next month, sweep
+    // whatever is left into savings and lock the month against new entries.
+    // All the prompts come first and the writes go in one transaction, so
+    // quitting part way leaves the month as it was.
+    pub(crate) fn close_month(&mut self, month: Option<&str>) -> Result<(), WalletError> {
+        let month = month_start(month)?;
+        let label = month.format("%B %Y").to_string();
+        self.ensure_unlocked(month)?;
+        let theme = ColorfulTheme::default();
+
+        let income = self.month_income(month)?;
+        let mut envelopes = self.load_envelopes(month)?;
+        let assigned: f64 = envelopes.iter().map(|e| e.assigned).sum();
+        let mut unbudgeted = income - assigned;
+
+        outln!("\nClosing {}", label);
+        outln!("{:<30} {:<15.2}", "Income", income);
+        outln!("{:<30} {:<15.2}", "Already assigned", assigned);
+        outln!("{:<30} {:<15.2}", "Unbudgeted", unbudgeted);
+        if unbudgeted < 0.0 {
+            outln!("Warning: envelopes are over-assigned by {:.2}", -unbudgeted);
+        }
+
+        // Step 1: give every unbudgeted rupee a job. Without prompts nothing
+        // new is assigned and nothing is swept, since both need a choice of
+        // ledger; balances still carry forward.
+        if !output::interactive() && unbudgeted > 0.005 {
+            outln!(
+                "Non-interactive: leaving {:.2} unassigned and unswept",
+                unbudgeted
+            );
+        }
+        let mut added = vec![0.0; envelopes.len()];
+        while output::interactive() && unbudgeted > 0.005 && !envelopes.is_empty() {
+            let mut items: Vec<String> = envelopes
+                .iter()
+                .map(|e| format!("{:<10} available {:.2}", e.code, e.available()))
+                .collect();
+            items.push("Done assigning (sweep the rest)".to_string());
+            let choice = Select::with_theme(&theme)
+                .with_prompt(format!("{:.2} left to assign", unbudgeted))
+                .items(&items)
+                .default(0)
+                .interact()
+                .unwrap_or(items.len() - 1);
+            if choice == envelopes.len() {
+                break;
+            }
+
+            let amount: f64 = Input::with_theme(&theme)
+                .with_prompt(format!("Assign to {}", envelopes[choice].code))
+                .default(unbudgeted)
+                .validate_with(|value: &f64| {
+                    if *value > 0.0 && *value <= unbudgeted + 0.005 {
+                        Ok(())
+                    } else {
+                        Err(format!("Enter an amount between 0 and {:.2}", unbudgeted))
+                    }
+                })
+                .interact_text()?;
+
+            added[choice] += amount;
+            envelopes[choice].assigned += amount;
+            unbudgeted -= amount;
+        }
+
+        // Step 2: pick where anything still unassigned is swept
+        let next_month = month.checked_add_months(Months::new(1)).unwrap();
+        let mut sweep = None;
+        if output::interactive() && unbudgeted > 0.005 {
+            let assets: Vec<String> = self
+                .client
+                .query(
+                    "SELECT code FROM ledgers WHERE kind = 'ASSET' ORDER BY code",
+                    &[],
+                )?
+                .iter()
+                .map(|row| row.get(0))
+                .collect();
+            let confirmed = !assets.is_empty()
+                && output::confirm(&format!("Sweep the unassigned {:.2}?", unbudgeted), true)
+                    .unwrap_or(false);
+            if confirmed {
+                let from = Select::with_theme(&theme)
+                    .with_prompt("Sweep from")
+                    .items(&assets)
+                    .default(0)
+                    .interact()?;
+                let to = Select::with_theme(&theme)
+                    .with_prompt("Sweep into")
+                    .items(&assets)
+                    .default(0)
+                    .interact()?;
+                if from == to {
+                    outln!("Sweep skipped: source and destination are the same ledger.");
+                } else {
+                    let mut entry = SpendEntry {
+                        patron: assets[from].clone(),
+                        outlay: assets[to].clone(),
+                        amount: unbudgeted,
+                        narration: format!("Month close sweep for {}", label),
+                        created_at: next_month.pred_opt().unwrap().and_hms_opt(23, 59, 59),
+                        ..Default::default()
+                    };
+                    let ledger_ids = [
+                        self.retrieve_ledger_id(&entry.patron)?,
+                        self.retrieve_ledger_id(&entry.outlay)?,
+                    ];
+                    self.apply_policies(ledger_ids, &mut entry)?;
+                    sweep = Some((ledger_ids, entry));
+                }
+            }
+        }
+
+        let mut transaction = self.client.transaction()?;
+        for (envelope, amount) in envelopes.iter().zip(added.iter()) {
+            if *amount > 0.0 {
+                transaction.execute(
+                    "INSERT INTO envelopes (ledger_id, month, assigned) VALUES ($1, $2, $3)
+                     ON CONFLICT (ledger_id, month)
+                     DO UPDATE SET assigned = envelopes.assigned + EXCLUDED.assigned,
+                                   updated_at = CURRENT_TIMESTAMP",
+                    &[&envelope.ledger_id, &month, amount],
+                )?;
+            }
+        }
+
+        // Step 3: carry each envelope's balance (or overspend) forward
+        let mut carried = 0.0;
+        for envelope in envelopes.iter() {
+            let balance = envelope.available();
+            carried += balance;
+            transaction.execute(
+                "INSERT INTO envelopes (ledger_id, month, carried_in) VALUES ($1, $2, $3)
+                 ON CONFLICT (ledger_id, month)
+                 DO UPDATE SET carried_in = EXCLUDED.carried_in,
+                               updated_at = CURRENT_TIMESTAMP",
+                &[&envelope.ledger_id, &next_month, &balance],
+            )?;
+        }
+
+        // Step 4: sweep and lock the month
+        let mut swept = 0.0;
+        if let Some(([patron_id, outlay_id], entry)) = sweep {
+            transaction.execute(
+                "INSERT INTO proceedings (cr_from, db_to, amount, narration, created_at, payee, project, tags)
+                 VALUES ($1, $2, $3::float8, $4, $5::timestamp, $6, $7, $8)",
+                &[
+                    &patron_id,
+                    &outlay_id,
+                    &entry.amount,
+                    &entry.narration,
+                    &entry.created_at,
+                    &entry.payee,
+                    &entry.project,
+                    &entry.tags,
+                ],
+            )?;
+            swept = entry.amount;
+        }
+        transaction.execute("INSERT INTO period_locks (month) VALUES ($1)", &[&month])?;
+        transaction.commit()?;
+
+        let spent: f64 = envelopes.iter().map(|e| e.spent).sum();
+        outln!("\nMonth Close Summary ({}):", label);
+        outln!(
+            "{:<10} {:<15} {:<15} {:<15} {:<15}",
+            "Code",
+            "Carried In",
+            "Assigned",
+            "Spent",
+            "Carry Out"
+        );
+        outln!("{:-<70}", "");
+        for envelope in envelopes.iter() {
+            outln!(
+                "{:<10} {:<15.2} {:<15.2} {:<15.2} {:<15.2}",
+                envelope.code,
+                envelope.carried_in,
+                envelope.assigned,
+                envelope.spent,
+                envelope.available()
+            );
+        }
+        outln!("{:-<70}", "");
+        outln!("{:<30} {:<15.2}", "Income", income);
+        outln!("{:<30} {:<15.2}", "Spent", spent);
+        outln!("{:<30} {:<15.2}", "Carried to next month", carried);
+        outln!("{:<30} {:<15.2}", "Swept", swept);
+        outln!("{:<30} {:<15.2}", "Left unassigned", unbudgeted - swept);
+        outln!("{} is now locked.", label);
+        Ok(())
+    }
+}
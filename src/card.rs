@@ -0,0 +1,225 @@
+use std::fs;
+
+use chrono::{Months, NaiveDate, NaiveDateTime};
+
+use crate::budget::{month_range, month_start};
+use crate::raster::{Canvas, Rgb};
+use crate::{dates, WalletDB, WalletError};
+
+const WIDTH: u32 = 640;
+const HEIGHT: u32 = 360;
+const BACKGROUND: Rgb = [24, 28, 40];
+const PANEL: Rgb = [36, 42, 58];
+const TEXT: Rgb = [236, 239, 244];
+const MUTED: Rgb = [140, 150, 170];
+const ACCENT: Rgb = [94, 200, 160];
+const WARN: Rgb = [236, 110, 100];
+const BARS_X: u32 = 420;
+
+// Everything shown on a month's report card
+struct CardData {
+    label: String,
+    income: f64,
+    spent: f64,
+    categories: Vec<(String, f64)>,
+    best_streak: u32,
+    current_streak: u32,
+}
+
+impl CardData {
+    fn savings_rate(&self) -> Option<f64> {
+        (self.income > 0.0).then(|| (self.income - self.spent) / self.income * 100.0)
+    }
+}
+
+impl WalletDB {
+    // Renders a compact PNG summary of a month for sharing. With `percent`
+    // set, absolute amounts are replaced by shares of income and spending.
+    pub(crate) fn generate_card(
+        &mut self,
+        month: Option<&str>,
+        cap: Option<f64>,
+        percent: bool,
+        output: &str,
+    ) -> Result<(), WalletError> {
+        let month = month_start(month)?;
+        let data = self.card_data(month, cap)?;
+        let png = render(&data, cap, percent).to_png();
+        fs::write(output, png)?;
+        outln!("Wrote report card for {} to {}", data.label, output);
+        Ok(())
+    }
+
+    // Net spend per EXPENSE ledger in [start, end), largest first
+    pub(crate) fn expense_totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<(String, f64)>, WalletError> {
+        let rows = self.client.query(
+            "
+            SELECT l.code,
+                   SUM(CASE WHEN p.db_to = l.id THEN p.amount ELSE -p.amount END)::float8 as spent
+            FROM proceedings p
+            JOIN ledgers l ON l.id = p.db_to OR l.id = p.cr_from
+            WHERE ledger_kind_at(l.id, p.created_at) = 'EXPENSE'
+                AND p.created_at >= $1::timestamp AND p.created_at < $2::timestamp
+                AND NOT p.pending
+            GROUP BY l.code
+            HAVING SUM(CASE WHEN p.db_to = l.id THEN p.amount ELSE -p.amount END) > 0
+            ORDER BY spent DESC
+            ",
+            &[&start, &end],
+        )?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    fn card_data(&mut self, month: NaiveDate, cap: Option<f64>) -> Result<CardData, WalletError> {
+        let (start, end) = month_range(month);
+        let income = self.month_income(month)?;
+
+        let categories = self.expense_totals(start, end)?;
+        let spent = categories.iter().map(|(_, amount)| amount).sum();
+
+        let rows = self.client.query(
+            "
+            SELECT DATE(p.created_at) as day,
+                   SUM(CASE WHEN p.db_to = l.id THEN p.amount ELSE -p.amount END)::float8 as spent
+            FROM proceedings p
+            JOIN ledgers l ON l.id = p.db_to OR l.id = p.cr_from
+            WHERE ledger_kind_at(l.id, p.created_at) = 'EXPENSE'
+                AND p.created_at >= $1::timestamp AND p.created_at < $2::timestamp
+                AND NOT p.pending
+            GROUP BY DATE(p.created_at)
+            ",
+            &[&start, &end],
+        )?;
+        let daily: Vec<(NaiveDate, f64)> =
+            rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+
+        // A streak day is a day with no spending, or within the cap if given
+        let today = dates::today();
+        let last = month
+            .checked_add_months(Months::new(1))
+            .and_then(|d| d.pred_opt())
+            .unwrap()
+            .min(today);
+        let (mut best_streak, mut current_streak) = (0, 0);
+        for day in month.iter_days().take_while(|day| *day <= last) {
+            let spent = daily
+                .iter()
+                .find(|(d, _)| *d == day)
+                .map(|(_, amount)| *amount)
+                .unwrap_or(0.0);
+            if spent <= cap.unwrap_or(0.0) {
+                current_streak += 1;
+                best_streak = best_streak.max(current_streak);
+            } else {
+                current_streak = 0;
+            }
+        }
+
+        Ok(CardData {
+            label: month.format("%B %Y").to_string(),
+            income,
+            spent,
+            categories,
+            best_streak,
+            current_streak,
+        })
+    }
+}
+
+fn render(data: &CardData, cap: Option<f64>, percent: bool) -> Canvas {
+    let mut canvas = Canvas::new(WIDTH, HEIGHT, BACKGROUND);
+    canvas.text(24, 24, 4, TEXT, &data.label);
+    canvas.text(24, 62, 2, MUTED, "SPENDLOG REPORT CARD");
+
+    // Left panel: headline numbers
+    canvas.fill_rect(24, 96, 260, 180, PANEL);
+    let rate = data.savings_rate();
+    let stats = if percent {
+        vec![
+            (
+                "SPENT OF INCOME",
+                rate.map(|r| format!("{:.0}%", 100.0 - r))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+            (
+                "SAVINGS RATE",
+                rate.map(|r| format!("{:.0}%", r))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]
+    } else {
+        vec![
+            ("INCOME", format!("{:.0}", data.income)),
+            ("SPENT", format!("{:.0}", data.spent)),
+            (
+                "SAVINGS RATE",
+                rate.map(|r| format!("{:.0}%", r))
+                    .unwrap_or_else(|| "-".to_string()),
+            ),
+        ]
+    };
+    for (i, (label, value)) in stats.iter().enumerate() {
+        let y = 110 + i as u32 * 56;
+        canvas.text(40, y, 2, MUTED, label);
+        let color = if *label == "SAVINGS RATE" && rate.is_some_and(|r| r < 0.0) {
+            WARN
+        } else {
+            TEXT
+        };
+        canvas.text(40, y + 20, 3, color, value);
+    }
+
+    // Right panel: top categories as bars. Codes too long for the gap before
+    // the bars are drawn at half size.
+    canvas.fill_rect(300, 96, 316, 180, PANEL);
+    canvas.text(316, 110, 2, MUTED, "TOP CATEGORIES");
+    let largest = data.categories.first().map(|(_, a)| *a).unwrap_or(0.0);
+    for (i, (code, amount)) in data.categories.iter().take(4).enumerate() {
+        let y = 140 + i as u32 * 32;
+        if Canvas::text_width(code, 2) <= BARS_X - 316 - 8 {
+            canvas.text(316, y, 2, TEXT, code);
+        } else {
+            canvas.text(316, y + 4, 1, TEXT, code);
+        }
+        let value = if percent {
+            format!("{:.0}%", amount / data.spent * 100.0)
+        } else {
+            format!("{:.0}", amount)
+        };
+        // Long values shorten the bar rather than run off the panel
+        let x = 600u32.saturating_sub(Canvas::text_width(&value, 2));
+        let bar = if largest > 0.0 {
+            (amount / largest * 120.0).round() as u32
+        } else {
+            0
+        };
+        let bar = bar.min(x.saturating_sub(BARS_X + 6)).max(2);
+        canvas.fill_rect(BARS_X, y, bar, 14, ACCENT);
+        canvas.text(x.max(BARS_X + bar + 6), y, 2, TEXT, &value);
+    }
+    if data.categories.is_empty() {
+        canvas.text(316, 140, 2, MUTED, "NO SPENDING");
+    }
+
+    // Footer: streaks
+    let streak = match cap {
+        Some(cap) if !percent => format!("UNDER {:.0}/DAY STREAK", cap),
+        Some(_) => "UNDER-CAP STREAK".to_string(),
+        None => "NO-SPEND STREAK".to_string(),
+    };
+    canvas.text(
+        24,
+        300,
+        2,
+        ACCENT,
+        &format!(
+            "{}: BEST {} DAYS, NOW {}",
+            streak, data.best_streak, data.current_streak
+        ),
+    );
+    canvas
+}
@@ -0,0 +1,357 @@
+// Shell completion scripts generated from the clap command tree. Each script
+// walks the words typed so far to find the subcommand and positional index
+// being completed, then offers subcommands, flags, enum values or, for
+// ledger arguments, the codes printed by the hidden `_complete-ledgers`.
+
+use clap::{Command, ValueEnum};
+
+const BIN: &str = "spendlog";
+
+// Arguments that take an existing ledger code, by subcommand path and arg id
+const LEDGER_ARGS: &[(&str, &str)] = &[
+    ("spend", "patron"),
+    ("spend", "outlay"),
+    ("template add", "patron"),
+    ("template add", "outlay"),
+    ("ledger-report", "code"),
+    ("ledger rekind", "code"),
+    ("ledger policy", "code"),
+    ("goal add", "ledger"),
+    ("snapshot take", "code"),
+    ("snapshot diff", "code"),
+    ("invoice add", "client"),
+    ("reconcile", "code"),
+    ("reconcile", "adjust_with"),
+    ("forget", "ledger"),
+    ("sum", "ledger"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+enum Values {
+    Ledgers,
+    List(Vec<String>),
+    Any,
+}
+
+// One subcommand (or the root) and what may follow it
+struct Node {
+    path: String,
+    subcommands: Vec<String>,
+    flags: Vec<String>,
+    options: Vec<(String, Values)>,
+    positionals: Vec<Values>,
+}
+
+fn collect(command: &Command, path: &str, nodes: &mut Vec<Node>) {
+    let sub_path = path.strip_prefix(BIN).unwrap_or(path).trim();
+    let values = |arg: &clap::Arg| {
+        if LEDGER_ARGS.contains(&(sub_path, arg.get_id().as_str())) {
+            return Values::Ledgers;
+        }
+        let possible: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|value| !value.is_hide_set())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        if possible.is_empty() {
+            Values::Any
+        } else {
+            Values::List(possible)
+        }
+    };
+
+    let mut node = Node {
+        path: path.to_string(),
+        subcommands: Vec::new(),
+        flags: Vec::new(),
+        options: Vec::new(),
+        positionals: Vec::new(),
+    };
+    for arg in command.get_arguments().filter(|arg| !arg.is_hide_set()) {
+        if arg.is_positional() {
+            node.positionals.push(values(arg));
+            continue;
+        }
+        let names: Vec<String> = arg
+            .get_long_and_visible_aliases()
+            .unwrap_or_default()
+            .iter()
+            .map(|long| format!("--{}", long))
+            .chain(arg.get_short().map(|short| format!("-{}", short)))
+            .collect();
+        for name in names {
+            if arg.get_action().takes_values() {
+                node.options.push((name.clone(), values(arg)));
+            }
+            node.flags.push(name);
+        }
+    }
+    for sub in command.get_subcommands().filter(|sub| !sub.is_hide_set()) {
+        node.subcommands.push(sub.get_name().to_string());
+        // `help <command>` mirrors the whole tree; only its name is offered
+        if sub.get_name() != "help" {
+            collect(sub, &format!("{} {}", path, sub.get_name()), nodes);
+        }
+    }
+    nodes.push(node);
+}
+
+// `"a"|"b"` for use as a case pattern
+fn patterns<'a>(items: impl Iterator<Item = &'a str>) -> String {
+    items
+        .map(|item| format!("\"{}\"", item))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+// Shell words expanding to the candidates for `values`
+fn sh_candidates(values: &Values) -> Option<String> {
+    match values {
+        Values::Ledgers => Some(format!("$({} _complete-ledgers 2>/dev/null)", BIN)),
+        Values::List(items) => Some(items.join(" ")),
+        Values::Any => None,
+    }
+}
+
+// Fish command printing the candidates for `values`, one per line
+fn fish_candidates(values: &Values) -> Option<String> {
+    match values {
+        Values::Ledgers => Some(format!("{} _complete-ledgers 2>/dev/null", BIN)),
+        Values::List(items) => Some(format!("printf '%s\\n' {}", items.join(" "))),
+        Values::Any => None,
+    }
+}
+
+// "<path> <option>" for every option that takes a value
+fn value_options(nodes: &[Node]) -> Vec<String> {
+    nodes
+        .iter()
+        .flat_map(|node| {
+            node.options
+                .iter()
+                .map(move |(name, _)| format!("{} {}", node.path, name))
+        })
+        .collect()
+}
+
+// "<path> <subcommand>" for every subcommand
+fn subcommand_paths(nodes: &[Node]) -> Vec<String> {
+    nodes
+        .iter()
+        .flat_map(|node| {
+            node.subcommands
+                .iter()
+                .map(move |sub| format!("{} {}", node.path, sub))
+        })
+        .collect()
+}
+
+pub fn generate(shell: Shell, command: &mut Command) -> String {
+    command.build();
+    let mut nodes = Vec::new();
+    collect(command, BIN, &mut nodes);
+    nodes.sort_by(|a, b| a.path.cmp(&b.path));
+    match shell {
+        Shell::Bash => sh_script(&nodes, false),
+        Shell::Zsh => sh_script(&nodes, true),
+        Shell::Fish => fish_script(&nodes),
+    }
+}
+
+// Bash and zsh share the same case-based body; only the word array and
+// how candidates are handed back differ
+fn sh_script(nodes: &[Node], zsh: bool) -> String {
+    let mut script = String::new();
+    let (words, current, first, finish) = if zsh {
+        script.push_str(&format!("#compdef {}\n\n", BIN));
+        (
+            "words",
+            "CURRENT",
+            "2",
+            "    compadd -- ${=candidates}\n}\n\n\
+             if [ \"$funcstack[1]\" = \"_spendlog\" ]; then\n    _spendlog \"$@\"\n\
+             else\n    compdef _spendlog spendlog\nfi\n",
+        )
+    } else {
+        (
+            "COMP_WORDS",
+            "COMP_CWORD",
+            "1",
+            "    COMPREPLY=($(compgen -W \"$candidates\" -- \"$cur\"))\n}\n\ncomplete -F _spendlog spendlog\n",
+        )
+    };
+    script.push_str("_spendlog() {\n");
+    script.push_str(&format!(
+        "    local cur=\"${{{w}[{c}]}}\" prev=\"${{{w}[{c}-1]}}\"\n",
+        w = words,
+        c = current
+    ));
+    script.push_str(&format!(
+        "    local cmdpath=\"{}\" positional=0 skip=0 i word candidates=\"\"\n",
+        BIN
+    ));
+    script.push_str(&format!(
+        "    for ((i = {}; i < {}; i++)); do\n        word=\"${{{}[i]}}\"\n",
+        first, current, words
+    ));
+    script.push_str(
+        "        if ((skip)); then\n            skip=0\n            continue\n        fi\n",
+    );
+    script.push_str("        case \"$cmdpath $word\" in\n");
+    let value_options = value_options(nodes);
+    if !value_options.is_empty() {
+        script.push_str(&format!(
+            "            {})\n                skip=1\n                continue\n                ;;\n",
+            patterns(value_options.iter().map(String::as_str))
+        ));
+    }
+    let subcommands = subcommand_paths(nodes);
+    script.push_str(&format!(
+        "            {})\n                cmdpath=\"$cmdpath $word\"\n                positional=0\n                continue\n                ;;\n",
+        patterns(subcommands.iter().map(String::as_str))
+    ));
+    script.push_str("        esac\n");
+    script.push_str("        [[ $word == -* ]] || positional=$((positional + 1))\n");
+    script.push_str("    done\n\n");
+
+    script.push_str("    if ((skip)); then\n        case \"$cmdpath $prev\" in\n");
+    for node in nodes {
+        for (name, values) in node.options.iter() {
+            if let Some(words) = sh_candidates(values) {
+                script.push_str(&format!(
+                    "            \"{} {}\") candidates=\"{}\" ;;\n",
+                    node.path, name, words
+                ));
+            }
+        }
+    }
+    script
+        .push_str("        esac\n    elif [[ $cur == -* ]]; then\n        case \"$cmdpath\" in\n");
+    for node in nodes.iter().filter(|node| !node.flags.is_empty()) {
+        script.push_str(&format!(
+            "            \"{}\") candidates=\"{}\" ;;\n",
+            node.path,
+            node.flags.join(" ")
+        ));
+    }
+    script.push_str("        esac\n    else\n        case \"$cmdpath $positional\" in\n");
+    for node in nodes {
+        for (index, values) in node.positionals.iter().enumerate() {
+            if let Some(words) = sh_candidates(values) {
+                script.push_str(&format!(
+                    "            \"{} {}\") candidates=\"{}\" ;;\n",
+                    node.path, index, words
+                ));
+            }
+        }
+    }
+    script.push_str(
+        "        esac\n        if ((positional == 0)); then\n            case \"$cmdpath\" in\n",
+    );
+    for node in nodes.iter().filter(|node| !node.subcommands.is_empty()) {
+        script.push_str(&format!(
+            "                \"{}\") candidates=\"$candidates {}\" ;;\n",
+            node.path,
+            node.subcommands.join(" ")
+        ));
+    }
+    script.push_str("            esac\n        fi\n    fi\n");
+    script.push_str(finish);
+    script
+}
+
+// Fish runs one function per completion and takes a candidate per line
+fn fish_script(nodes: &[Node]) -> String {
+    let quoted = |items: Vec<String>| {
+        items
+            .iter()
+            .map(|item| format!("'{}'", item))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+
+    let mut script = String::new();
+    script.push_str("function __spendlog_complete\n");
+    script.push_str("    set -l tokens (commandline -opc)\n");
+    script.push_str("    set -l cur (commandline -ct)\n");
+    script.push_str(&format!(
+        "    set -l cmdpath {}\n    set -l positional 0\n    set -l skip 0\n",
+        BIN
+    ));
+    script.push_str("    for word in $tokens[2..-1]\n");
+    script.push_str(
+        "        if test $skip -eq 1\n            set skip 0\n            continue\n        end\n",
+    );
+    script.push_str("        switch \"$cmdpath $word\"\n");
+    let value_options = value_options(nodes);
+    if !value_options.is_empty() {
+        script.push_str(&format!(
+            "            case {}\n                set skip 1\n                continue\n",
+            quoted(value_options)
+        ));
+    }
+    script.push_str(&format!(
+        "            case {}\n                set cmdpath \"$cmdpath $word\"\n                set positional 0\n                continue\n",
+        quoted(subcommand_paths(nodes))
+    ));
+    script.push_str("        end\n");
+    script.push_str(
+        "        string match -q -- '-*' $word; or set positional (math $positional + 1)\n",
+    );
+    script.push_str("    end\n\n");
+
+    script.push_str("    if test $skip -eq 1\n        switch \"$cmdpath $tokens[-1]\"\n");
+    for node in nodes {
+        for (name, values) in node.options.iter() {
+            if let Some(command) = fish_candidates(values) {
+                script.push_str(&format!(
+                    "            case '{} {}'\n                {}\n",
+                    node.path, name, command
+                ));
+            }
+        }
+    }
+    script.push_str(
+        "        end\n    else if string match -q -- '-*' $cur\n        switch $cmdpath\n",
+    );
+    for node in nodes.iter().filter(|node| !node.flags.is_empty()) {
+        script.push_str(&format!(
+            "            case '{}'\n                printf '%s\\n' {}\n",
+            node.path,
+            node.flags.join(" ")
+        ));
+    }
+    script.push_str("        end\n    else\n        switch \"$cmdpath $positional\"\n");
+    for node in nodes {
+        for (index, values) in node.positionals.iter().enumerate() {
+            if let Some(command) = fish_candidates(values) {
+                script.push_str(&format!(
+                    "            case '{} {}'\n                {}\n",
+                    node.path, index, command
+                ));
+            }
+        }
+    }
+    script
+        .push_str("        end\n        if test $positional -eq 0\n            switch $cmdpath\n");
+    for node in nodes.iter().filter(|node| !node.subcommands.is_empty()) {
+        script.push_str(&format!(
+            "                case '{}'\n                    printf '%s\\n' {}\n",
+            node.path,
+            node.subcommands.join(" ")
+        ));
+    }
+    script.push_str("            end\n        end\n    end\nend\n\n");
+    script.push_str(&format!(
+        "complete -c {} -f -a '(__spendlog_complete)'\n",
+        BIN
+    ));
+    script
+}
@@ -0,0 +1,52 @@
+use std::env;
+
+use chrono::Duration;
+
+const DEFAULT_DATABASE_URL: &str =
+    "host=localhost user=postgres password=postgres dbname=wallet_db";
+
+// Runtime settings, read from the environment (and `.env` via dotenv).
+pub struct Config {
+    pub database_url: String,
+    pub pool_size: usize,
+    // Spends matching an existing one within this long are flagged as
+    // likely duplicates; zero turns the check off
+    pub dedup_window: Duration,
+    // Wallet used when --profile is not given
+    pub profile: Option<String>,
+    // Bearer token `serve` requires when --token is not given
+    pub api_token: Option<String>,
+    // Same as --low-memory, for a daemon or server started without flags
+    pub low_memory: bool,
+    // IANA zone (e.g. Asia/Kolkata) days are counted in when --tz is not
+    // given; without either the database server's zone is used
+    pub timezone: Option<String>,
+    // Extra lines under the spending report, as "name = expression" pairs
+    // separated by ';' (see footer.rs)
+    pub report_footer: Option<String>,
+}
+
+impl Config {
+    pub fn load() -> Self {
+        dotenv::dotenv().ok();
+        Config {
+            database_url: env::var("DATABASE_URL")
+                .unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string()),
+            pool_size: env::var("SPENDLOG_POOL_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4),
+            dedup_window: Duration::minutes(
+                env::var("SPENDLOG_DEDUP_WINDOW")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(60),
+            ),
+            profile: env::var("SPENDLOG_PROFILE").ok(),
+            api_token: env::var("SPENDLOG_API_TOKEN").ok(),
+            low_memory: env::var("SPENDLOG_LOW_MEMORY").is_ok_and(|v| v == "1" || v == "true"),
+            timezone: env::var("SPENDLOG_TZ").ok(),
+            report_footer: env::var("SPENDLOG_REPORT_FOOTER").ok(),
+        }
+    }
+}
@@ -0,0 +1,237 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::NaiveDateTime;
+use colored::Colorize;
+use console::Term;
+
+use crate::budget::month_start;
+use crate::{dates, ReportPeriod, WalletDB, WalletError};
+
+// Inner width of a panel, excluding its border
+const PANEL_WIDTH: usize = 38;
+// Rows shown per panel before the rest is summarised
+const PANEL_ROWS: usize = 6;
+
+// A titled block of pre-formatted lines
+pub(crate) struct Panel {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl WalletDB {
+    // Runs the today, month, budget and recent queries on separate pooled
+    // connections at the same time and prints them as one screen of panels.
+    pub(crate) fn dashboard(&mut self) -> Result<(), WalletError> {
+        let panels = dashboard_panels(self)?;
+        outln!(
+            "\n{}",
+            format!(
+                "Spendlog Dashboard - {}",
+                dates::now().format("%Y-%m-%d %H:%M")
+            )
+            .bold()
+        );
+        out!("{}", render(&panels, terminal_width()));
+        Ok(())
+    }
+
+    // Read-only wall display: one panel at a time, centred full-screen,
+    // moving on every `refresh`. Data is re-read at the start of each cycle
+    // and a failed read is shown rather than ending the loop, so it runs
+    // until killed.
+    pub(crate) fn kiosk(&mut self, refresh: Duration) -> Result<(), WalletError> {
+        let mut panels = Vec::new();
+        let mut next = 0;
+        loop {
+            if next == 0 {
+                panels = dashboard_panels(self).unwrap_or_else(|e| {
+                    vec![Panel {
+                        title: "Dashboard unavailable".to_string(),
+                        lines: vec![e.to_string()],
+                    }]
+                });
+            }
+            let (height, width) = Term::stdout().size();
+            let header = format!(
+                "Spendlog - {}  ({}/{})",
+                dates::now().format("%Y-%m-%d %H:%M"),
+                next + 1,
+                panels.len()
+            );
+            let body = render(std::slice::from_ref(&panels[next]), width as usize);
+            let top = (height as usize).saturating_sub(body.lines().count() + 2) / 2;
+            let left = " ".repeat((width as usize).saturating_sub(PANEL_WIDTH + 4) / 2);
+
+            // Clear the screen and home the cursor
+            out!("\x1b[2J\x1b[H");
+            out!("{}{}{}\n", "\n".repeat(top), left, header.bold());
+            out!("\n");
+            for line in body.lines() {
+                out!("{}{}\n", left, line);
+            }
+            next = (next + 1) % panels.len();
+            thread::sleep(refresh);
+        }
+    }
+}
+
+// Builds the panels on separate pooled connections at once, or in turn on
+// `db`'s own connection when the pool has no other to give
+pub(crate) fn dashboard_panels(db: &mut WalletDB) -> Result<Vec<Panel>, WalletError> {
+    type Builder = fn(&mut WalletDB) -> Result<Panel, WalletError>;
+    let builders: [Builder; 4] = [today_panel, month_panel, budget_panel, recent_panel];
+    if db.pool.max_size() == 1 {
+        return builders.iter().map(|build| build(db)).collect();
+    }
+    let pool = &db.pool;
+    thread::scope(|scope| {
+        let handles: Vec<_> = builders
+            .iter()
+            .map(|build| scope.spawn(move || build(&mut WalletDB::from_pool(pool)?)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("dashboard panel panicked"))
+            .collect()
+    })
+}
+
+fn spending_panel(db: &mut WalletDB, period: ReportPeriod) -> Result<Panel, WalletError> {
+    let (start, end, label) = period.bounds()?;
+    let totals: Vec<(String, f64)> = db
+        .spending_totals(start, end)?
+        .into_iter()
+        .filter(|(_, _, amount)| amount.abs() >= 0.005)
+        .map(|(code, _, amount)| (code, amount))
+        .collect();
+    let mut lines = Vec::new();
+    for (code, amount) in totals.iter().take(PANEL_ROWS) {
+        lines.push(format!("{:<20} {:>17.2}", code, amount));
+    }
+    if totals.len() > PANEL_ROWS {
+        lines.push(format!("... {} more", totals.len() - PANEL_ROWS));
+    }
+    if totals.is_empty() {
+        lines.push("No activity".to_string());
+    }
+    let total = totals.iter().fold(0.0, |sum, (_, amount)| sum + amount);
+    lines.push(format!("{:-<38}", ""));
+    lines.push(format!("{:<20} {:>17.2}", "Total", total));
+    Ok(Panel {
+        title: label,
+        lines,
+    })
+}
+
+fn today_panel(db: &mut WalletDB) -> Result<Panel, WalletError> {
+    spending_panel(db, ReportPeriod::Today)
+}
+
+fn month_panel(db: &mut WalletDB) -> Result<Panel, WalletError> {
+    spending_panel(db, ReportPeriod::Month)
+}
+
+fn budget_panel(db: &mut WalletDB) -> Result<Panel, WalletError> {
+    let month = month_start(None)?;
+    let envelopes = db.load_envelopes(month)?;
+    let mut lines = vec![format!(
+        "{:<8} {:>9} {:>9} {:>9}",
+        "Code", "Assigned", "Spent", "Left"
+    )];
+    for envelope in envelopes.iter().take(PANEL_ROWS) {
+        lines.push(format!(
+            "{:<8} {:>9.2} {:>9.2} {:>9.2}",
+            envelope.code,
+            envelope.assigned,
+            envelope.spent,
+            envelope.available()
+        ));
+    }
+    if envelopes.len() > PANEL_ROWS {
+        lines.push(format!("... {} more", envelopes.len() - PANEL_ROWS));
+    }
+    if envelopes.is_empty() {
+        lines.push("No expense ledgers".to_string());
+    }
+    Ok(Panel {
+        title: format!("Budget ({})", month.format("%B")),
+        lines,
+    })
+}
+
+fn recent_panel(db: &mut WalletDB) -> Result<Panel, WalletError> {
+    let rows = db.client.query(
+        "
+        SELECT p.created_at::timestamp,
+               (SELECT code FROM ledgers WHERE id = p.cr_from),
+               (SELECT code FROM ledgers WHERE id = p.db_to),
+               p.amount::float8
+        FROM proceedings p
+        ORDER BY p.created_at DESC
+        LIMIT $1
+        ",
+        &[&(PANEL_ROWS as i64)],
+    )?;
+    let mut lines = Vec::new();
+    for row in rows.iter() {
+        let created_at: NaiveDateTime = row.get(0);
+        let from: String = row.get(1);
+        let to: String = row.get(2);
+        let amount: f64 = row.get(3);
+        lines.push(format!(
+            "{:<6} {:<20} {:>10.2}",
+            created_at.format("%m-%d").to_string(),
+            format!("{} -> {}", from, to),
+            amount
+        ));
+    }
+    if lines.is_empty() {
+        lines.push("No transactions yet".to_string());
+    }
+    Ok(Panel {
+        title: format!("Recent (as of {})", dates::now().format("%H:%M")),
+        lines,
+    })
+}
+
+fn terminal_width() -> usize {
+    Term::stdout()
+        .size_checked()
+        .map(|(_, width)| width as usize)
+        .unwrap_or(80)
+}
+
+// Lays panels out in as many side-by-side columns as `width` allows
+pub(crate) fn render(panels: &[Panel], width: usize) -> String {
+    let columns = (width / (PANEL_WIDTH + 4)).clamp(1, panels.len().max(1));
+    let border = format!("+{:-<w$}+", "", w = PANEL_WIDTH + 2);
+    let mut out = String::new();
+    for row in panels.chunks(columns) {
+        let height = row.iter().map(|panel| panel.lines.len()).max().unwrap_or(0);
+        let mut lines: Vec<String> = vec![String::new(); height + 4];
+        for panel in row {
+            let title = format!("{:<w$}", fit(&panel.title), w = PANEL_WIDTH);
+            lines[0].push_str(&border);
+            lines[1].push_str(&format!("| {} |", title.bold()));
+            lines[2].push_str(&border);
+            for i in 0..height {
+                let line = panel.lines.get(i).map(|l| fit(l)).unwrap_or_default();
+                lines[i + 3].push_str(&format!("| {:<w$} |", line, w = PANEL_WIDTH));
+            }
+            lines[height + 3].push_str(&border);
+            for line in lines.iter_mut() {
+                line.push(' ');
+            }
+        }
+        for line in lines {
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+fn fit(line: &str) -> String {
+    line.chars().take(PANEL_WIDTH).collect()
+}
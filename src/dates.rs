@@ -0,0 +1,408 @@
+use std::env;
+
+use chrono::{Datelike, Days, Duration, Local, Month, Months, NaiveDate, NaiveDateTime, Weekday};
+
+use crate::WalletError;
+
+// Makes the user's time zone the process's local one, so "today" ends where
+// the reports' days do. Called once per command, before any thread starts;
+// chrono then reads the zone's rules from the system tz database and applies
+// DST itself, so a long-running serve or kiosk follows the changes.
+pub fn set_zone(zone: &str) {
+    env::set_var("TZ", zone);
+}
+
+// Wall-clock time in the user's time zone; the machine's when none was set
+// (the demo)
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+pub fn today() -> NaiveDate {
+    now().date()
+}
+
+const DATE_HINT: &str =
+    "Use YYYY-MM-DD, 'today', 'yesterday', 'last monday', '2d ago', 'apr 15' or '04-15'";
+
+// Resolves a user supplied date into the single day it names. Month-only
+// inputs such as "apr" are rejected since they do not pick out one day.
+pub fn parse_day(input: &str, today: NaiveDate) -> Result<NaiveDate, WalletError> {
+    let (start, end) = parse_span(input, today)?;
+    if start != end {
+        return Err(WalletError::InvalidDate(format!(
+            "'{}' covers {} to {}; give a single day",
+            input, start, end
+        )));
+    }
+    Ok(start)
+}
+
+// Like parse_day, but a date given without a year ("nov 1", "11-01") names
+// its next occurrence rather than its last, for dates that lie ahead such
+// as cheque clearing dates. Relative inputs ("yesterday") are unaffected.
+pub fn parse_upcoming_day(input: &str, today: NaiveDate) -> Result<NaiveDate, WalletError> {
+    let normalized = input.trim().to_lowercase().replace(',', " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+    if relative_day(input, &words, today)?.is_some() {
+        return parse_day(input, today);
+    }
+    // Year inference picks the latest date on or before its reference day,
+    // so a reference just under a year ahead gives the next occurrence
+    let horizon = today
+        .checked_add_months(Months::new(12))
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(today);
+    parse_day(input, horizon)
+}
+
+// Resolves a user supplied date into an inclusive (start, end) range of days.
+// Day inputs give a one-day range; month inputs ("apr", "2025-04") give the
+// whole month so they can be used directly with --date, --from and --to.
+pub fn parse_span(input: &str, today: NaiveDate) -> Result<(NaiveDate, NaiveDate), WalletError> {
+    let normalized = input.trim().to_lowercase().replace(',', " ");
+    let words: Vec<&str> = normalized.split_whitespace().collect();
+
+    if let Some(day) = relative_day(input, &words, today)? {
+        return Ok((day, day));
+    }
+
+    if words.len() == 1 && parse_month(words[0]).is_none() {
+        numeric_span(input, words[0], today)
+    } else {
+        named_month_span(input, &words, today)
+    }
+}
+
+// Days named relative to today: "today", "yesterday", "last monday",
+// "monday", "2d ago", "3 weeks ago"
+fn relative_day(
+    input: &str,
+    words: &[&str],
+    today: NaiveDate,
+) -> Result<Option<NaiveDate>, WalletError> {
+    Ok(match words {
+        [] => {
+            return Err(WalletError::InvalidDate(format!(
+                "Empty date. {}",
+                DATE_HINT
+            )))
+        }
+        ["today"] => Some(today),
+        ["yesterday"] => Some(today - Duration::days(1)),
+        ["last", name] => Some(weekday_before(today, parse_weekday(input, name)?, true)),
+        [count, "ago"] => Some(ago(input, count, "", today)?),
+        [count, unit, "ago"] => Some(ago(input, count, unit, today)?),
+        [name] if name.parse::<Weekday>().is_ok() => {
+            Some(weekday_before(today, name.parse().unwrap(), false))
+        }
+        _ => None,
+    })
+}
+
+fn unrecognised(input: &str) -> WalletError {
+    WalletError::InvalidDate(format!("Unrecognised date '{}'. {}", input, DATE_HINT))
+}
+
+fn parse_weekday(input: &str, name: &str) -> Result<Weekday, WalletError> {
+    name.parse::<Weekday>().map_err(|_| unrecognised(input))
+}
+
+// Most recent occurrence of `weekday` on or before `today`. With `strict`
+// set, today itself is skipped so "last monday" on a Monday means a week ago.
+fn weekday_before(today: NaiveDate, weekday: Weekday, strict: bool) -> NaiveDate {
+    let mut back =
+        (7 + today.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    if strict && back == 0 {
+        back = 7;
+    }
+    today - Duration::days(back as i64)
+}
+
+// Handles "2d ago", "2 d ago", "3 weeks ago" and "1 month ago".
+fn ago(input: &str, count: &str, unit: &str, today: NaiveDate) -> Result<NaiveDate, WalletError> {
+    let split = count
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(count.len());
+    let (digits, suffix) = count.split_at(split);
+    let unit = if unit.is_empty() { suffix } else { unit };
+    if !suffix.is_empty() && suffix != unit {
+        return Err(unrecognised(input));
+    }
+    let n: u32 = digits.parse().map_err(|_| unrecognised(input))?;
+
+    match unit {
+        "d" | "day" | "days" => today.checked_sub_days(Days::new(n as u64)),
+        "w" | "week" | "weeks" => today.checked_sub_days(Days::new(n as u64 * 7)),
+        "m" | "month" | "months" => today.checked_sub_months(Months::new(n)),
+        _ => None,
+    }
+    .ok_or_else(|| unrecognised(input))
+}
+
+// Handles the purely numeric forms: YYYY-MM-DD, YYYY-MM, MM-DD and the
+// slashed D/M, M/D and D/M/YYYY forms (rejected when the order is ambiguous).
+fn numeric_span(
+    input: &str,
+    word: &str,
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), WalletError> {
+    if let Ok(date) = NaiveDate::parse_from_str(word, "%Y-%m-%d") {
+        return Ok((date, date));
+    }
+
+    let separator = if word.contains('/') { '/' } else { '-' };
+    let parts: Vec<&str> = word.split(separator).collect();
+    if parts
+        .iter()
+        .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return Err(unrecognised(input));
+    }
+    let numbers: Vec<u32> = parts
+        .iter()
+        .map(|p| p.parse().map_err(|_| unrecognised(input)))
+        .collect::<Result<_, _>>()?;
+
+    match (separator, numbers.as_slice()) {
+        ('-', [year, month]) if parts[0].len() == 4 => month_span(input, *year as i32, *month),
+        ('-', [year, month, day]) if parts[0].len() == 4 => {
+            single_day(input, *year as i32, *month, *day)
+        }
+        ('-', [month, day]) => {
+            let year = infer_year(*month, Some(*day), today);
+            single_day(input, year, *month, *day)
+        }
+        ('/', [a, b]) => {
+            let (month, day) = slash_order(input, *a, *b)?;
+            single_day(input, infer_year(month, Some(day), today), month, day)
+        }
+        ('/', [a, b, year]) if parts[2].len() == 4 => {
+            let (month, day) = slash_order(input, *a, *b)?;
+            single_day(input, *year as i32, month, day)
+        }
+        _ => Err(unrecognised(input)),
+    }
+}
+
+// Decides whether "a/b" is day/month or month/day, refusing to guess when
+// both readings are valid and differ.
+fn slash_order(input: &str, a: u32, b: u32) -> Result<(u32, u32), WalletError> {
+    match (a <= 12, b <= 12) {
+        (true, true) if a != b => Err(WalletError::InvalidDate(format!(
+            "Ambiguous date '{}': could be {} {} or {} {}. Use YYYY-MM-DD or a month name",
+            input,
+            month_abbrev(a),
+            b,
+            month_abbrev(b),
+            a
+        ))),
+        (true, _) => Ok((a, b)),
+        (false, true) => Ok((b, a)),
+        (false, false) => Err(unrecognised(input)),
+    }
+}
+
+// Handles month-name forms: "apr", "apr 2024", "apr 15", "15 apr",
+// "apr 15 2024" and "15 apr 2024".
+fn named_month_span(
+    input: &str,
+    words: &[&str],
+    today: NaiveDate,
+) -> Result<(NaiveDate, NaiveDate), WalletError> {
+    let month_at = words.iter().position(|w| parse_month(w).is_some());
+    let Some(month_at) = month_at else {
+        return Err(unrecognised(input));
+    };
+    let month = parse_month(words[month_at]).unwrap();
+    let rest: Vec<&str> = words
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != month_at)
+        .map(|(_, w)| *w)
+        .collect();
+
+    let number = |w: &str| -> Result<u32, WalletError> {
+        w.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .map_err(|_| unrecognised(input))
+    };
+
+    match rest.as_slice() {
+        [] => month_span(input, infer_year(month, None, today), month),
+        [year] if year.len() == 4 && month_at == 0 => {
+            month_span(input, number(year)? as i32, month)
+        }
+        [day] => {
+            let day = number(day)?;
+            single_day(input, infer_year(month, Some(day), today), month, day)
+        }
+        [day, year] if year.len() == 4 && (month_at == 0 || month_at == 1) => {
+            single_day(input, number(year)? as i32, month, number(day)?)
+        }
+        _ => Err(unrecognised(input)),
+    }
+}
+
+// Parses a full or abbreviated month name into its number (1-12).
+pub fn parse_month(name: &str) -> Option<u32> {
+    name.parse::<Month>().ok().map(|m| m.number_from_month())
+}
+
+fn month_abbrev(month: u32) -> String {
+    Month::try_from(month as u8)
+        .map(|m| m.name()[..3].to_string())
+        .unwrap_or_else(|_| month.to_string())
+}
+
+// Partial dates without a year refer to the most recent such date, so
+// "dec 20" typed in January means last December.
+fn infer_year(month: u32, day: Option<u32>, today: NaiveDate) -> i32 {
+    let in_future = match day {
+        Some(day) => (month, day) > (today.month(), today.day()),
+        None => month > today.month(),
+    };
+    if in_future {
+        today.year() - 1
+    } else {
+        today.year()
+    }
+}
+
+fn single_day(
+    input: &str,
+    year: i32,
+    month: u32,
+    day: u32,
+) -> Result<(NaiveDate, NaiveDate), WalletError> {
+    let date = NaiveDate::from_ymd_opt(year, month, day)
+        .ok_or_else(|| WalletError::InvalidDate(format!("No such date '{}'", input)))?;
+    Ok((date, date))
+}
+
+fn month_span(input: &str, year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), WalletError> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| WalletError::InvalidDate(format!("No such month '{}'", input)))?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .ok_or_else(|| WalletError::InvalidDate(format!("No such month '{}'", input)))?;
+    Ok((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A Wednesday
+    fn today() -> NaiveDate {
+        day(2025, 4, 16)
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn parse(input: &str) -> NaiveDate {
+        parse_day(input, today()).unwrap_or_else(|e| panic!("{}: {}", input, e))
+    }
+
+    fn message(input: &str) -> String {
+        match parse_span(input, today()) {
+            Err(WalletError::InvalidDate(message)) => message,
+            other => panic!("{}: expected an invalid date, got {:?}", input, other),
+        }
+    }
+
+    #[test]
+    fn relative_days() {
+        assert_eq!(parse("today"), today());
+        assert_eq!(parse(" Yesterday "), day(2025, 4, 15));
+        assert_eq!(parse("monday"), day(2025, 4, 14));
+        assert_eq!(parse("wednesday"), today());
+        assert_eq!(parse("last monday"), day(2025, 4, 14));
+        assert_eq!(parse("last wednesday"), day(2025, 4, 9));
+        assert_eq!(parse("2d ago"), day(2025, 4, 14));
+        assert_eq!(parse("2 d ago"), day(2025, 4, 14));
+        assert_eq!(parse("3 weeks ago"), day(2025, 3, 26));
+        assert_eq!(parse("1 month ago"), day(2025, 3, 16));
+        assert!(message("2x ago").starts_with("Unrecognised"));
+        assert!(message("2d weeks ago").starts_with("Unrecognised"));
+        assert!(message("4000000000d ago").starts_with("Unrecognised"));
+        assert!(message("4000000000 weeks ago").starts_with("Unrecognised"));
+        assert!(message("last someday").starts_with("Unrecognised"));
+        assert!(message("  ").starts_with("Empty date"));
+    }
+
+    #[test]
+    fn numeric_dates() {
+        assert_eq!(parse("2025-04-10"), day(2025, 4, 10));
+        assert_eq!(parse("04-15"), day(2025, 4, 15));
+        // Without a year, a date still to come this year means last year's
+        assert_eq!(parse("04-17"), day(2024, 4, 17));
+        assert_eq!(parse("12-20"), day(2024, 12, 20));
+        assert_eq!(
+            parse_span("2024-02", today()).unwrap(),
+            (day(2024, 2, 1), day(2024, 2, 29))
+        );
+        assert!(message("02-30").starts_with("No such date"));
+        assert!(message("2025-13").starts_with("No such month"));
+        assert!(message("2025-02-30").starts_with("No such date"));
+    }
+
+    #[test]
+    fn slashed_dates() {
+        assert_eq!(parse("25/12"), day(2024, 12, 25));
+        assert_eq!(parse("12/25"), day(2024, 12, 25));
+        assert_eq!(parse("4/4"), day(2025, 4, 4));
+        assert_eq!(parse("25/12/2023"), day(2023, 12, 25));
+        assert!(message("3/4").starts_with("Ambiguous date '3/4'"));
+        assert!(message("13/13").starts_with("Unrecognised"));
+    }
+
+    #[test]
+    fn month_names() {
+        assert_eq!(parse("apr 15"), day(2025, 4, 15));
+        assert_eq!(parse("15 April"), day(2025, 4, 15));
+        assert_eq!(parse("may 1st"), day(2024, 5, 1));
+        assert_eq!(parse("apr 15, 2024"), day(2024, 4, 15));
+        assert_eq!(parse("15 apr 2024"), day(2024, 4, 15));
+        assert_eq!(
+            parse_span("apr", today()).unwrap(),
+            (day(2025, 4, 1), day(2025, 4, 30))
+        );
+        assert_eq!(
+            parse_span("may", today()).unwrap(),
+            (day(2024, 5, 1), day(2024, 5, 31))
+        );
+        assert_eq!(
+            parse_span("Feb 2024", today()).unwrap(),
+            (day(2024, 2, 1), day(2024, 2, 29))
+        );
+        assert!(message("apr 31").starts_with("No such date"));
+        assert!(message("apr 15 16").starts_with("Unrecognised"));
+    }
+
+    #[test]
+    fn single_days_only() {
+        match parse_day("apr", today()) {
+            Err(WalletError::InvalidDate(message)) => {
+                assert_eq!(
+                    message,
+                    "'apr' covers 2025-04-01 to 2025-04-30; give a single day"
+                )
+            }
+            other => panic!("expected an invalid date, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn upcoming_days() {
+        let upcoming = |input| parse_upcoming_day(input, today()).unwrap();
+        assert_eq!(upcoming("apr 20"), day(2025, 4, 20));
+        assert_eq!(upcoming("apr 10"), day(2026, 4, 10));
+        assert_eq!(upcoming("04-16"), today());
+        assert_eq!(upcoming("2025-01-01"), day(2025, 1, 1));
+        assert_eq!(upcoming("yesterday"), day(2025, 4, 15));
+    }
+}
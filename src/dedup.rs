@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, Timelike};
+use colored::Colorize;
+use dialoguer::{theme::ColorfulTheme, MultiSelect};
+
+use crate::{output, SpendEntry, WalletDB, WalletError};
+
+// Existing proceedings between the same ledgers with the same amount and
+// narration within the dedup window of the entry (of now when undated). An
+// entry dated at midnight has no time of day, so its whole day is matched.
+const MATCHES_QUERY: &str = "
+    SELECT p.id, p.created_at::timestamp
+    FROM proceedings p
+    WHERE p.cr_from = $1 AND p.db_to = $2
+        AND p.amount::float8 = $3::float8
+        AND lower(trim(p.narration)) = lower(trim($4))
+        AND p.created_at >= COALESCE($5::timestamp, LOCALTIMESTAMP) - make_interval(secs => $7)
+        AND p.created_at <= COALESCE($6::timestamp, LOCALTIMESTAMP) + make_interval(secs => $7)
+    ORDER BY p.created_at, p.id
+";
+
+fn describe(entry: &SpendEntry) -> String {
+    format!(
+        "{} -> {}: {:.2} ({})",
+        entry.patron, entry.outlay, entry.amount, entry.narration
+    )
+}
+
+impl WalletDB {
+    fn find_duplicates(
+        &mut self,
+        ids: [i32; 2],
+        entry: &SpendEntry,
+    ) -> Result<Vec<(i32, NaiveDateTime)>, WalletError> {
+        let start = entry.created_at;
+        let end = start
+            .filter(|at| at.num_seconds_from_midnight() == 0)
+            .map(|day| day + Duration::days(1) - Duration::seconds(1))
+            .or(start);
+        let window = self.dedup_window.num_seconds() as f64;
+        let rows = self.client.query(
+            MATCHES_QUERY,
+            &[
+                &ids[0],
+                &ids[1],
+                &entry.amount,
+                &entry.narration,
+                &start,
+                &end,
+                &window,
+            ],
+        )?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    // Warns when `entry` looks like a spend that was already recorded and
+    // asks whether to record it anyway
+    pub(crate) fn confirm_not_duplicate(
+        &mut self,
+        entry: &SpendEntry,
+    ) -> Result<bool, WalletError> {
+        if self.dedup_window <= Duration::zero() {
+            return Ok(true);
+        }
+        let ids = [
+            self.retrieve_ledger_id(&entry.patron)?,
+            self.retrieve_ledger_id(&entry.outlay)?,
+        ];
+        let matches = self.find_duplicates(ids, entry)?;
+        if matches.is_empty() {
+            return Ok(true);
+        }
+
+        outln!(
+            "{}",
+            format!("Possible duplicate of {}", describe(entry)).yellow()
+        );
+        for (id, created_at) in matches.iter() {
+            outln!(
+                "  #{} recorded {}",
+                id,
+                created_at.format("%Y-%m-%d %H:%M:%S")
+            );
+        }
+        Ok(output::confirm("Record it anyway?", false)?)
+    }
+
+    // Drops import entries that match an existing proceeding, once confirmed
+    pub(crate) fn skip_duplicates(
+        &mut self,
+        entries: Vec<SpendEntry>,
+    ) -> Result<Vec<SpendEntry>, WalletError> {
+        if self.dedup_window <= Duration::zero() {
+            return Ok(entries);
+        }
+        let mut ledger_ids = HashMap::new();
+        let mut duplicates = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let ids = [
+                self.cached_ledger_id(&mut ledger_ids, &entry.patron)?,
+                self.cached_ledger_id(&mut ledger_ids, &entry.outlay)?,
+            ];
+            if let Some((id, _)) = self.find_duplicates(ids, entry)?.first() {
+                duplicates.push((index, *id));
+            }
+        }
+        if duplicates.is_empty() {
+            return Ok(entries);
+        }
+
+        outln!(
+            "{}",
+            format!("{} entries look already recorded:", duplicates.len()).yellow()
+        );
+        for (index, id) in duplicates.iter() {
+            let entry = &entries[*index];
+            outln!(
+                "  {} {} (matches #{})",
+                entry
+                    .created_at
+                    .map(|at| at.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+                describe(entry),
+                id
+            );
+        }
+        let skip = output::confirm("Skip them?", true)?;
+        if !skip {
+            return Ok(entries);
+        }
+        Ok(entries
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| !duplicates.iter().any(|(skipped, _)| skipped == index))
+            .map(|(_, entry)| entry)
+            .collect())
+    }
+
+    // Lists proceedings that repeat an earlier one within the window and
+    // removes the ones picked from the list
+    pub(crate) fn dedup(&mut self, window: Option<i64>) -> Result<(), WalletError> {
+        let window = window.map(Duration::minutes).unwrap_or(self.dedup_window);
+        if window <= Duration::zero() {
+            outln!("Duplicate detection is off; pass --window or set SPENDLOG_DEDUP_WINDOW");
+            return Ok(());
+        }
+        let rows = self.client.query(
+            "
+            SELECT d.id, d.created_at::timestamp, f.code, t.code, d.amount::float8, d.narration, d.prev_id
+            FROM (
+                SELECT p.*,
+                       LAG(p.id) OVER w AS prev_id,
+                       LAG(p.created_at) OVER w AS prev_at
+                FROM proceedings p
+                WINDOW w AS (
+                    PARTITION BY p.cr_from, p.db_to, p.amount, lower(trim(p.narration))
+                    ORDER BY p.created_at, p.id
+                )
+            ) d
+            JOIN ledgers f ON f.id = d.cr_from
+            JOIN ledgers t ON t.id = d.db_to
+            WHERE d.prev_at IS NOT NULL
+                AND d.created_at - d.prev_at <= make_interval(secs => $1)
+            ORDER BY d.created_at, d.id
+            ",
+            &[&(window.num_seconds() as f64)],
+        )?;
+
+        if rows.is_empty() {
+            outln!(
+                "No suspected duplicates within {} minutes",
+                window.num_minutes()
+            );
+            return Ok(());
+        }
+
+        outln!("\nSuspected Duplicates:");
+        let mut candidates = Vec::with_capacity(rows.len());
+        for row in rows.iter() {
+            let id: i32 = row.get(0);
+            let created_at: NaiveDateTime = row.get(1);
+            let prev_id: i32 = row.get(6);
+            let line = format!(
+                "{:<6} {:<20} {:<10} {:<10} {:<12.2} {:<30} duplicate of #{}",
+                id,
+                created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+                row.get::<_, String>(2),
+                row.get::<_, String>(3),
+                row.get::<_, f64>(4),
+                row.get::<_, String>(5),
+                prev_id
+            );
+            candidates.push((id, created_at, prev_id, line));
+        }
+
+        if !output::interactive() {
+            for (_, _, _, line) in candidates.iter() {
+                outln!("{}", line);
+            }
+            outln!("Run without --yes to choose which to remove.");
+            return Ok(());
+        }
+
+        let lines: Vec<&str> = candidates
+            .iter()
+            .map(|(_, _, _, line)| line.as_str())
+            .collect();
+        let selected = MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("Select the transactions to remove (space to select, enter to confirm)")
+            .items(&lines)
+            .interact()?;
+        if selected.is_empty() {
+            outln!("Nothing removed.");
+            return Ok(());
+        }
+
+        for index in selected.iter() {
+            self.ensure_unlocked(candidates[*index].1.date())?;
+        }
+        let ids: Vec<i32> = selected.iter().map(|index| candidates[*index].0).collect();
+        let earlier: HashMap<i32, i32> = candidates
+            .iter()
+            .map(|(id, _, prev_id, _)| (*id, *prev_id))
+            .collect();
+
+        let mut transaction = self.client.transaction()?;
+        for id in ids.iter() {
+            // Payments against invoices move to the entry this one repeats,
+            // or further back if that one is being removed too
+            let mut kept = earlier[id];
+            while ids.contains(&kept) {
+                kept = earlier[&kept];
+            }
+            transaction.execute(
+                "INSERT INTO invoice_payments (invoice_id, proceeding_id, amount)
+                 SELECT invoice_id, $1, amount FROM invoice_payments WHERE proceeding_id = $2
+                 ON CONFLICT (invoice_id, proceeding_id)
+                 DO UPDATE SET amount = invoice_payments.amount + EXCLUDED.amount",
+                &[&kept, id],
+            )?;
+        }
+        transaction.execute(
+            "DELETE FROM invoice_payments WHERE proceeding_id = ANY($1)",
+            &[&ids],
+        )?;
+        let removed = transaction.execute("DELETE FROM proceedings WHERE id = ANY($1)", &[&ids])?;
+        transaction.commit()?;
+        outln!("Removed {} duplicate transactions", removed);
+        Ok(())
+    }
+}
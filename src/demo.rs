@@ -0,0 +1,278 @@
+// Sandbox over made-up data held in a MemoryStore. Nothing is read from or
+// written to a database, so it can be tried before one is set up.
+
+use std::io::{self, BufRead, Write};
+use std::iter;
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use clap::Parser;
+
+use crate::fx::{FxArgs, FxColumn};
+use crate::store::{self, Ledger, MemoryStore, WalletStore};
+use crate::{dates, tax, Cli, Commands, ReportPeriod, SpendEntry, WalletError};
+
+// Ledgers the demo starts with: code, name, sort and kind
+const LEDGERS: [(&str, &str, &str, &str); 9] = [
+    ("BANK", "Bank", "DR", "ASSET"),
+    ("BILLS", "Utilities", "DR", "EXPENSE"),
+    ("CARD", "Credit Card", "CR", "LIABILITY"),
+    ("CASH", "Cash", "DR", "ASSET"),
+    ("FOOD", "Food", "DR", "EXPENSE"),
+    ("FUEL", "Fuel", "DR", "EXPENSE"),
+    ("FUN", "Entertainment", "DR", "EXPENSE"),
+    ("RENT", "Rent", "DR", "EXPENSE"),
+    ("SALARY", "Salary", "CR", "INCOME"),
+];
+// Days of activity generated, ending today
+const SEED_DAYS: i64 = 60;
+const MEALS: [&str; 5] = ["Groceries", "Lunch", "Zomato", "Swiggy", "Coffee"];
+
+// Small linear congruential generator, so the demo data is the same every
+// run without a dependency on rand
+struct Seed(u64);
+
+impl Seed {
+    fn next(&mut self, below: u64) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (self.0 >> 33) % below
+    }
+
+    fn amount(&mut self, low: u64, high: u64) -> f64 {
+        (low + self.next(high - low)) as f64
+    }
+}
+
+// Salary, rent, bills, card payments and everyday spending over the last
+// SEED_DAYS days
+fn seed() -> Result<MemoryStore, WalletError> {
+    let mut store = MemoryStore::default();
+    for (code, name, sort, kind) in LEDGERS {
+        store.insert_ledger(&Ledger {
+            code: code.to_string(),
+            name: name.to_string(),
+            description: String::new(),
+            sort: sort.to_string(),
+            kind: kind.to_string(),
+        })?;
+    }
+
+    let now = dates::now();
+    let today = now.date();
+    let mut seed = Seed(2024);
+    let mut entries = Vec::new();
+    let mut spend =
+        |day: NaiveDate, hour: u64, patron: &str, outlay: &str, amount: f64, narration: &str| {
+            let at = day.and_hms_opt(hour as u32, 0, 0).unwrap().min(now);
+            entries.push(SpendEntry {
+                patron: patron.to_string(),
+                outlay: outlay.to_string(),
+                amount,
+                narration: narration.to_string(),
+                created_at: Some(at),
+                ..Default::default()
+            });
+        };
+    for offset in (0..SEED_DAYS).rev() {
+        let day = today - Duration::days(offset);
+        match day.day() {
+            1 => spend(day, 9, "SALARY", "BANK", 85000.0, "Salary"),
+            5 => spend(day, 10, "BANK", "RENT", 22000.0, "Rent"),
+            10 => spend(day, 11, "BANK", "CARD", 15000.0, "Card payment"),
+            12 => spend(
+                day,
+                18,
+                "BANK",
+                "BILLS",
+                seed.amount(1800, 3200),
+                "Electricity",
+            ),
+            15 => spend(day, 12, "BANK", "CASH", 3000.0, "ATM withdrawal"),
+            _ => {}
+        }
+        let meal = MEALS[seed.next(MEALS.len() as u64) as usize];
+        let patron = if seed.next(3) == 0 { "CASH" } else { "CARD" };
+        let hour = 8 + seed.next(14);
+        spend(day, hour, patron, "FOOD", seed.amount(80, 600), meal);
+        if day.weekday() == Weekday::Sat {
+            spend(day, 17, "CARD", "FUEL", seed.amount(1500, 3000), "Fuel");
+            spend(day, 20, "CARD", "FUN", seed.amount(300, 1500), "Movie");
+        }
+    }
+    store.insert_spends(&entries)?;
+    Ok(store)
+}
+
+// Runs `args` as one spendlog command against fresh demo data, or without
+// any starts a shell that keeps its data until it exits
+pub(crate) fn demo(args: &[String]) -> Result<(), WalletError> {
+    // No clock connection is set, so days follow the machine's zone
+    let mut store = seed()?;
+
+    if !args.is_empty() {
+        let cli =
+            Cli::try_parse_from(iter::once("spendlog").chain(args.iter().map(String::as_str)))
+                .unwrap_or_else(|e| e.exit());
+        return run_command(&mut store, cli.command);
+    }
+
+    outln!("Spendlog demo: made-up data kept in memory, nothing is saved.");
+    outln!(
+        "Try 'list-ledgers', 'report month', 'last' or 'spend CASH FOOD 120 Tea'; 'exit' leaves."
+    );
+    let stdin = io::stdin();
+    loop {
+        out!("demo> ");
+        io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+        let args = split(&line);
+        match args.first().map(String::as_str) {
+            None => continue,
+            Some("exit") | Some("quit") => break,
+            Some(_) => {}
+        }
+        match Cli::try_parse_from(iter::once("spendlog").chain(args.iter().map(String::as_str))) {
+            Ok(cli) => {
+                if let Err(e) = run_command(&mut store, cli.command) {
+                    eprintln!("Error: {}", e);
+                }
+            }
+            Err(e) => {
+                let _ = e.print();
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_command(store: &mut MemoryStore, command: Commands) -> Result<(), WalletError> {
+    match command {
+        Commands::AddLedger {
+            code,
+            name,
+            description,
+            sort,
+            kind,
+        } => {
+            store.insert_ledger(&Ledger {
+                code: code.clone(),
+                name: name.clone(),
+                description,
+                sort,
+                kind,
+            })?;
+            outln!("Added ledger: {} - {}", code, name);
+        }
+        Commands::Spend {
+            patron: Some(patron),
+            outlay: Some(outlay),
+            amount: Some(amount),
+            narration,
+            date,
+            payee,
+            project,
+            tags,
+            tax,
+            tax_amount,
+            clears_on,
+            pending,
+        } => {
+            let today = dates::today();
+            let created_at = date
+                .map(|date_str| dates::parse_day(&date_str, today))
+                .transpose()?
+                .map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+            let clears_on = clears_on
+                .map(|date_str| dates::parse_upcoming_day(&date_str, today))
+                .transpose()?;
+            let tax_rate = tax.map(|rate| tax::parse_rate(&rate)).transpose()?;
+            let entry = SpendEntry {
+                patron,
+                outlay,
+                amount,
+                narration: narration.unwrap_or_default(),
+                created_at,
+                pending: pending || clears_on.is_some(),
+                clears_on,
+                payee,
+                project,
+                tags,
+                tax_amount: tax_rate
+                    .map(|rate| tax::included_tax(amount, rate))
+                    .or(tax_amount),
+                tax_rate,
+                cleared_at: None,
+            };
+            store.insert_spends(std::slice::from_ref(&entry))?;
+            outln!(
+                "Added spending: {} -> {}: {} ({})",
+                entry.patron,
+                entry.outlay,
+                entry.amount,
+                entry.narration
+            );
+        }
+        Commands::Spend { .. } => {
+            outln!("The demo has no prompts; give PATRON, OUTLAY and AMOUNT.");
+        }
+        Commands::ListLedgers => store::list_ledgers(store)?,
+        Commands::Report {
+            command: None,
+            period,
+            date,
+            from,
+            to,
+            group_by: None,
+            fx: FxArgs { also_in: None },
+        } => {
+            let period = ReportPeriod::from_args("demo report", period, date, from, to)?;
+            store::spending_report(store, &period, &FxColumn::default())?;
+        }
+        Commands::Report { .. } => {
+            outln!("Grouped, converted, tax and interest reports need a database; the demo has the plain report.")
+        }
+        Commands::Last { paging } => store::recent_report(store, &paging)?,
+        Commands::Demo { .. } => outln!("Already in the demo."),
+        _ => outln!(
+            "Only add-ledger, spend, list-ledgers, report and last work in the demo; the rest needs a database."
+        ),
+    }
+    Ok(())
+}
+
+// Splits a shell line into arguments, keeping quoted text together
+fn split(line: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+    let mut in_arg = false;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_arg = true;
+            }
+            None if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    args
+}
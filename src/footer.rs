@@ -0,0 +1,259 @@
+// User-defined lines under a report, set as SPENDLOG_REPORT_FOOTER, e.g.
+// "daily_average = total / days; vs_budget = budget - total". Each line is
+// a name and an arithmetic expression (+ - * / and parentheses) over the
+// report's aggregates and the lines defined before it.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::{dates, ReportPeriod, WalletDB, WalletError};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Op(char),
+}
+
+fn tokenize(expr: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expr.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek().filter(|c| c.is_ascii_digit() || **c == '.') {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", number))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut name = String::new();
+            while let Some(&c) = chars
+                .peek()
+                .filter(|c| c.is_ascii_alphanumeric() || **c == '_')
+            {
+                name.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Name(name));
+        } else if "+-*/()".contains(c) {
+            tokens.push(Token::Op(c));
+            chars.next();
+        } else {
+            return Err(format!("unexpected '{}'", c));
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent over the tokens, evaluating as it goes:
+//   expr   = term (('+' | '-') term)*
+//   term   = factor (('*' | '/') factor)*
+//   factor = number | name | '-' factor | '(' expr ')'
+struct Evaluator<'a> {
+    tokens: Vec<Token>,
+    next: usize,
+    vars: &'a HashMap<String, f64>,
+}
+
+impl Evaluator<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.next)
+    }
+
+    fn take(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.next).cloned();
+        self.next += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<f64, String> {
+        let mut value = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek().cloned() {
+            self.next += 1;
+            let rhs = self.term()?;
+            value = if op == '+' { value + rhs } else { value - rhs };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> Result<f64, String> {
+        let mut value = self.factor()?;
+        while let Some(Token::Op(op @ ('*' | '/'))) = self.peek().cloned() {
+            self.next += 1;
+            let rhs = self.factor()?;
+            value = if op == '*' { value * rhs } else { value / rhs };
+        }
+        Ok(value)
+    }
+
+    fn factor(&mut self) -> Result<f64, String> {
+        match self.take() {
+            Some(Token::Number(value)) => Ok(value),
+            Some(Token::Name(name)) => self.vars.get(&name).copied().ok_or_else(|| {
+                let mut known: Vec<&str> = self.vars.keys().map(String::as_str).collect();
+                known.sort();
+                format!("unknown name '{}' (known: {})", name, known.join(", "))
+            }),
+            Some(Token::Op('-')) => Ok(-self.factor()?),
+            Some(Token::Op('(')) => {
+                let value = self.expr()?;
+                match self.take() {
+                    Some(Token::Op(')')) => Ok(value),
+                    _ => Err("missing ')'".to_string()),
+                }
+            }
+            Some(Token::Op(op)) => Err(format!("unexpected '{}'", op)),
+            None => Err("expression ends early".to_string()),
+        }
+    }
+}
+
+fn evaluate(expr: &str, vars: &HashMap<String, f64>) -> Result<f64, String> {
+    let mut evaluator = Evaluator {
+        tokens: tokenize(expr)?,
+        next: 0,
+        vars,
+    };
+    let value = evaluator.expr()?;
+    match evaluator.peek() {
+        None => Ok(value),
+        Some(Token::Number(value)) => Err(format!("unexpected {}", value)),
+        Some(Token::Name(name)) => Err(format!("unexpected '{}'", name)),
+        Some(Token::Op(op)) => Err(format!("unexpected '{}'", op)),
+    }
+}
+
+// Prints the footer lines with labels padded to `width`, to line up with
+// the report's grand total. A line that does not parse is reported on
+// stderr and skipped rather than failing the report.
+pub(crate) fn print(spec: &str, width: usize, vars: HashMap<String, f64>) {
+    let mut vars = vars;
+    for line in spec.split([';', '\n']).map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let Some((name, expr)) = line.split_once('=') else {
+            eprintln!(
+                "Skipping footer line '{}': expected NAME = EXPRESSION",
+                line
+            );
+            continue;
+        };
+        let name = name.trim();
+        match evaluate(expr, &vars) {
+            Ok(value) if value.is_finite() => {
+                outln!("{:<width$} {:<15.2}", name, value, width = width);
+                vars.insert(name.to_string(), value);
+            }
+            Ok(_) => outln!("{:<width$} {:<15}", name, "n/a", width = width),
+            Err(e) => eprintln!("Skipping footer line '{}': {}", line, e),
+        }
+    }
+}
+
+impl WalletDB {
+    // The names footer expressions can use for a report over `period`
+    // totalling `total`: total, count (transactions), days (in the period up
+    // to today, from the first transaction for 'all') and budget (assigned
+    // to envelopes in the months covered)
+    pub(crate) fn footer_vars(
+        &mut self,
+        period: &ReportPeriod,
+        total: f64,
+    ) -> Result<HashMap<String, f64>, WalletError> {
+        let (start, end, _) = period.bounds()?;
+        let row = self.client.query_one(
+            "SELECT
+                 (SELECT COUNT(*) FROM proceedings
+                  WHERE NOT pending AND created_at >= $1::timestamp
+                      AND ($2::timestamp IS NULL OR created_at <= $2::timestamp)),
+                 (SELECT MIN(created_at)::date FROM proceedings
+                  WHERE NOT pending AND created_at >= $1::timestamp),
+                 (SELECT COALESCE(SUM(assigned), 0)::float8 FROM envelopes
+                  WHERE month >= date_trunc('month', $1::timestamp)::date
+                      AND month <= COALESCE($2::timestamp, LOCALTIMESTAMP)::date)",
+            &[&start, &end],
+        )?;
+        let count: i64 = row.get(0);
+        let first: Option<NaiveDate> = row.get(1);
+        let last = end.map(|end| end.date()).unwrap_or_else(dates::today);
+        let from = match (period, first) {
+            (ReportPeriod::All, Some(first)) => first,
+            _ => start.date(),
+        };
+        let days = ((last - from).num_days() + 1).max(0);
+
+        Ok(HashMap::from([
+            ("total".to_string(), total),
+            ("count".to_string(), count as f64),
+            ("days".to_string(), days as f64),
+            ("budget".to_string(), row.get(2)),
+        ]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars() -> HashMap<String, f64> {
+        HashMap::from([("total".to_string(), 300.0), ("days".to_string(), 30.0)])
+    }
+
+    fn eval(expr: &str) -> Result<f64, String> {
+        evaluate(expr, &vars())
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("total/ 2.5").unwrap(),
+            [
+                Token::Name("total".to_string()),
+                Token::Op('/'),
+                Token::Number(2.5)
+            ]
+        );
+        assert_eq!(tokenize("1.2.3").unwrap_err(), "'1.2.3' is not a number");
+        assert_eq!(tokenize("total % 2").unwrap_err(), "unexpected '%'");
+    }
+
+    #[test]
+    fn precedence_and_grouping() {
+        assert_eq!(eval("1 + 2 * 3"), Ok(7.0));
+        assert_eq!(eval("(1 + 2) * 3"), Ok(9.0));
+        assert_eq!(eval("10 - 4 - 3"), Ok(3.0));
+        assert_eq!(eval("total / days / 2"), Ok(5.0));
+        assert_eq!(eval("-total + 1"), Ok(-299.0));
+        assert_eq!(eval("2 * -(days - 40)"), Ok(20.0));
+        assert_eq!(eval("--3"), Ok(3.0));
+    }
+
+    #[test]
+    fn division_by_zero_is_not_finite() {
+        assert_eq!(eval("total / 0"), Ok(f64::INFINITY));
+        assert!(eval("0 / 0").unwrap().is_nan());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            eval("total + budget"),
+            Err("unknown name 'budget' (known: days, total)".to_string())
+        );
+        assert_eq!(eval("total +"), Err("expression ends early".to_string()));
+        assert_eq!(eval("(total"), Err("missing ')'".to_string()));
+        assert_eq!(eval("total days"), Err("unexpected 'days'".to_string()));
+        assert_eq!(eval("total 2"), Err("unexpected 2".to_string()));
+        assert_eq!(eval("total )"), Err("unexpected ')'".to_string()));
+        assert_eq!(eval("* 2"), Err("unexpected '*'".to_string()));
+        assert_eq!(eval(""), Err("expression ends early".to_string()));
+    }
+}
@@ -0,0 +1,234 @@
+use chrono::NaiveDateTime;
+
+use crate::{output, WalletDB, WalletError};
+
+// Replaces narrations, names and notes that could identify the party
+const FORGOTTEN: &str = "[forgotten]";
+// Entries listed in the preview before the rest is summarised
+const PREVIEW_ROWS: usize = 10;
+
+// Proceedings tied to the party: those with the payee (case-insensitive) or
+// those touching the ledger, whichever of $1 / $2 is given
+const MATCHES: &str = "((lower(p.payee) = lower($1)) OR p.cr_from = $2 OR p.db_to = $2)";
+
+impl WalletDB {
+    // Erases what identifies a payee or ledger. By default matching entries
+    // keep their amounts but lose their narration and payee, and a ledger
+    // loses its name, description, invoice notes and template narrations.
+    // With `remove` the entries are deleted and replaced by one consolidated
+    // entry per ledger pair and month, so every balance and monthly total
+    // stays the same.
+    pub(crate) fn forget(
+        &mut self,
+        payee: Option<&str>,
+        ledger: Option<&str>,
+        remove: bool,
+    ) -> Result<(), WalletError> {
+        let ledger_id = ledger
+            .map(|code| self.retrieve_ledger_id(code))
+            .transpose()?;
+        let party = match (payee, ledger) {
+            (Some(payee), _) => format!("payee {}", payee),
+            (None, Some(code)) => format!("ledger {}", code),
+            (None, None) => unreachable!("clap requires --payee or --ledger"),
+        };
+
+        let rows = self.client.query(
+            &format!(
+                "
+                SELECT p.id, p.created_at::timestamp, f.code, t.code, p.amount::float8, p.narration
+                FROM proceedings p
+                JOIN ledgers f ON f.id = p.cr_from
+                JOIN ledgers t ON t.id = p.db_to
+                WHERE {}
+                ORDER BY p.created_at, p.id
+                ",
+                MATCHES
+            ),
+            &[&payee, &ledger_id],
+        )?;
+
+        outln!("\nForgetting {}", party);
+        outln!(
+            "{:<6} {:<12} {:<10} {:<10} {:<15} {:<30}",
+            "Id",
+            "Date",
+            "From",
+            "To",
+            "Amount",
+            "Narration"
+        );
+        outln!("{:-<86}", "");
+        for row in rows.iter().take(PREVIEW_ROWS) {
+            let created_at: NaiveDateTime = row.get(1);
+            outln!(
+                "{:<6} {:<12} {:<10} {:<10} {:<15.2} {:<30}",
+                row.get::<_, i32>(0),
+                created_at.format("%Y-%m-%d").to_string(),
+                row.get::<_, String>(2),
+                row.get::<_, String>(3),
+                row.get::<_, f64>(4),
+                row.get::<_, String>(5)
+            );
+        }
+        if rows.len() > PREVIEW_ROWS {
+            outln!("... {} more", rows.len() - PREVIEW_ROWS);
+        }
+        if rows.is_empty() {
+            outln!("No transactions");
+        }
+        outln!("{:-<86}", "");
+        if remove {
+            outln!(
+                "{} transactions will be deleted and replaced by consolidated entries.",
+                rows.len()
+            );
+        } else {
+            outln!(
+                "{} transactions will keep their amounts but lose their narration and payee.",
+                rows.len()
+            );
+        }
+        if let Some(code) = ledger {
+            outln!(
+                "Ledger {} keeps its code; its name, description, invoice notes and template narrations are erased.",
+                code
+            );
+        }
+        if rows.is_empty() && ledger.is_none() {
+            return Ok(());
+        }
+
+        let confirmed =
+            output::confirm(&format!("Forget {}? This cannot be undone.", party), false)?;
+        if !confirmed {
+            outln!("Nothing forgotten.");
+            return Ok(());
+        }
+
+        if remove {
+            // Entries are consolidated per month, each dated within the month
+            // it replaces, so every month being emptied must be open
+            let months = self.client.query(
+                &format!(
+                    "SELECT DISTINCT date_trunc('month', p.created_at)::date
+                     FROM proceedings p WHERE {}",
+                    MATCHES
+                ),
+                &[&payee, &ledger_id],
+            )?;
+            for month in months.iter() {
+                self.ensure_unlocked(month.get(0))?;
+            }
+        }
+
+        let mut transaction = self.client.transaction()?;
+        let mut consolidated = 0;
+        if remove {
+            let groups = transaction.query(
+                &format!(
+                    "
+                    SELECT p.cr_from, p.db_to, p.pending,
+                           SUM(p.amount)::float8,
+                           MAX(p.created_at)::timestamp,
+                           SUM(p.tax_amount)::float8,
+                           CASE WHEN bool_and(p.cleared_at IS NOT NULL) THEN MAX(p.cleared_at)::timestamp END,
+                           array_agg(p.id)
+                    FROM proceedings p
+                    WHERE {}
+                    GROUP BY p.cr_from, p.db_to, p.pending, date_trunc('month', p.created_at)
+                    ",
+                    MATCHES
+                ),
+                &[&payee, &ledger_id],
+            )?;
+            for group in groups.iter() {
+                let ids: Vec<i32> = group.get(7);
+                let narration = format!("Consolidated {} forgotten transactions", ids.len());
+                let id: i32 = transaction
+                    .query_one(
+                        "INSERT INTO proceedings
+                             (cr_from, db_to, pending, amount, narration, created_at, tax_amount, cleared_at)
+                         VALUES ($1, $2, $3, $4::float8, $5, $6::timestamp, $7::float8, $8::timestamp)
+                         RETURNING id",
+                        &[
+                            &group.get::<_, i32>(0),
+                            &group.get::<_, i32>(1),
+                            &group.get::<_, bool>(2),
+                            &group.get::<_, f64>(3),
+                            &narration,
+                            &group.get::<_, NaiveDateTime>(4),
+                            &group.get::<_, Option<f64>>(5),
+                            &group.get::<_, Option<NaiveDateTime>>(6),
+                        ],
+                    )?
+                    .get(0);
+                // Payments against invoices move to the consolidated entry
+                transaction.execute(
+                    "INSERT INTO invoice_payments (invoice_id, proceeding_id, amount)
+                     SELECT invoice_id, $1, SUM(amount)
+                     FROM invoice_payments WHERE proceeding_id = ANY($2)
+                     GROUP BY invoice_id",
+                    &[&id, &ids],
+                )?;
+                transaction.execute(
+                    "DELETE FROM invoice_payments WHERE proceeding_id = ANY($1)",
+                    &[&ids],
+                )?;
+                // So do links to entries outside the group; deleting the
+                // originals drops their old links
+                transaction.execute(
+                    "INSERT INTO links (from_id, to_id, kind)
+                     SELECT CASE WHEN from_id = ANY($2) THEN $1 ELSE from_id END,
+                            CASE WHEN to_id = ANY($2) THEN $1 ELSE to_id END,
+                            kind
+                     FROM links
+                     WHERE (from_id = ANY($2)) <> (to_id = ANY($2))
+                     ON CONFLICT (from_id, to_id) DO NOTHING",
+                    &[&id, &ids],
+                )?;
+                transaction.execute("DELETE FROM proceedings WHERE id = ANY($1)", &[&ids])?;
+                consolidated += 1;
+            }
+        } else {
+            transaction.execute(
+                &format!(
+                    "UPDATE proceedings p
+                     SET narration = $3, payee = NULL, updated_at = CURRENT_TIMESTAMP
+                     WHERE {}",
+                    MATCHES
+                ),
+                &[&payee, &ledger_id, &FORGOTTEN],
+            )?;
+        }
+        if let Some(ledger_id) = ledger_id {
+            transaction.execute(
+                "UPDATE ledgers
+                 SET name = $2, description = NULL, updated_at = CURRENT_TIMESTAMP
+                 WHERE id = $1",
+                &[&ledger_id, &FORGOTTEN],
+            )?;
+            transaction.execute(
+                "UPDATE invoices SET note = NULL WHERE ledger_id = $1",
+                &[&ledger_id],
+            )?;
+            transaction.execute(
+                "UPDATE templates SET narration = NULL WHERE patron_id = $1 OR outlay_id = $1",
+                &[&ledger_id],
+            )?;
+        }
+        transaction.commit()?;
+
+        if remove {
+            outln!(
+                "Forgot {}: {} transactions replaced by {} consolidated entries",
+                party,
+                rows.len(),
+                consolidated
+            );
+        } else {
+            outln!("Forgot {}: {} transactions anonymized", party, rows.len());
+        }
+        Ok(())
+    }
+}
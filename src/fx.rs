@@ -0,0 +1,214 @@
+use chrono::NaiveDate;
+use clap::Args;
+
+use crate::{dates, WalletDB, WalletError};
+
+// Currency every amount is recorded in; fx rates are quoted against it
+pub const BASE_CURRENCY: &str = "INR";
+
+// Width of the converted column, matching the reports' amount columns
+const WIDTH: usize = 15;
+
+// `--also-in` flag shared by the reports
+#[derive(Args, Clone, Debug, Default)]
+pub struct FxArgs {
+    #[arg(
+        long,
+        value_name = "CURRENCY",
+        help = "Add a column converted to this currency at the period's average rate, or the latest one before it"
+    )]
+    pub also_in: Option<String>,
+}
+
+struct Conversion {
+    currency: String,
+    // Units of the base currency per unit of `currency`
+    rate: f64,
+    basis: String,
+}
+
+// The extra column of a report run with --also-in; every method renders
+// nothing when the flag was not given (the default)
+#[derive(Default)]
+pub struct FxColumn(Option<Conversion>);
+
+impl FxColumn {
+    pub fn header(&self) -> String {
+        match &self.0 {
+            Some(conversion) => format!(" {:<WIDTH$}", conversion.currency),
+            None => String::new(),
+        }
+    }
+
+    pub fn cell(&self, amount: f64) -> String {
+        match &self.0 {
+            Some(conversion) => format!(" {:<WIDTH$.2}", amount / conversion.rate),
+            None => String::new(),
+        }
+    }
+
+    // Blank cell for lines with nothing to convert
+    pub fn blank(&self) -> String {
+        match &self.0 {
+            Some(_) => format!(" {:<WIDTH$}", ""),
+            None => String::new(),
+        }
+    }
+
+    // Divider line `width` wide plus the column
+    pub fn rule(&self, width: usize) -> String {
+        let width = width + self.0.as_ref().map_or(0, |_| WIDTH + 1);
+        "-".repeat(width)
+    }
+
+    // Prints the rate used, under the report
+    pub fn footnote(&self) {
+        if let Some(conversion) = &self.0 {
+            outln!(
+                "{} at {:.4} {} per {} ({})",
+                conversion.currency,
+                conversion.rate,
+                BASE_CURRENCY,
+                conversion.currency,
+                conversion.basis
+            );
+        }
+    }
+}
+
+// Upper-cased ISO 4217 code, e.g. "usd" -> "USD"
+pub fn parse_currency(input: &str) -> Result<String, WalletError> {
+    let code = input.trim().to_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code)
+    } else {
+        Err(WalletError::FxRate(format!(
+            "'{}' is not a currency code such as USD",
+            input
+        )))
+    }
+}
+
+impl WalletDB {
+    // Conversion for a report covering `start` to `end` (today when open):
+    // the average of the rates recorded in the period, or failing that the
+    // latest rate recorded before it
+    pub(crate) fn fx_column(
+        &mut self,
+        args: &FxArgs,
+        start: NaiveDate,
+        end: Option<NaiveDate>,
+    ) -> Result<FxColumn, WalletError> {
+        let Some(currency) = &args.also_in else {
+            return Ok(FxColumn(None));
+        };
+        let currency = parse_currency(currency)?;
+        if currency == BASE_CURRENCY {
+            return Ok(FxColumn(Some(Conversion {
+                currency,
+                rate: 1.0,
+                basis: "base currency".to_string(),
+            })));
+        }
+        let end = end.unwrap_or_else(dates::today);
+
+        let average = self.client.query_one(
+            "SELECT AVG(rate)::float8, COUNT(*), MIN(rate_on), MAX(rate_on)
+             FROM fx_rates
+             WHERE currency = $1 AND rate_on >= $2 AND rate_on <= $3",
+            &[&currency, &start, &end],
+        )?;
+        let count: i64 = average.get(1);
+        if count > 0 {
+            let first: NaiveDate = average.get(2);
+            let last: NaiveDate = average.get(3);
+            let basis = if count == 1 {
+                format!("rate of {}", first)
+            } else {
+                format!("average of {} rates, {} to {}", count, first, last)
+            };
+            return Ok(FxColumn(Some(Conversion {
+                currency,
+                rate: average.get(0),
+                basis,
+            })));
+        }
+
+        let latest = self
+            .client
+            .query_opt(
+                "SELECT rate::float8, rate_on FROM fx_rates
+                 WHERE currency = $1 AND rate_on <= $2
+                 ORDER BY rate_on DESC
+                 LIMIT 1",
+                &[&currency, &end],
+            )?
+            .ok_or_else(|| {
+                WalletError::FxRate(format!(
+                    "no {} rate on or before {}; add one with `fx set {} RATE`",
+                    currency, end, currency
+                ))
+            })?;
+        let rate_on: NaiveDate = latest.get(1);
+        Ok(FxColumn(Some(Conversion {
+            currency,
+            rate: latest.get(0),
+            basis: format!("latest rate, {}", rate_on),
+        })))
+    }
+
+    pub(crate) fn set_fx_rate(
+        &mut self,
+        currency: &str,
+        rate: f64,
+        on: NaiveDate,
+    ) -> Result<(), WalletError> {
+        let currency = parse_currency(currency)?;
+        if rate <= 0.0 {
+            return Err(WalletError::FxRate("Rate must be positive".to_string()));
+        }
+        if currency == BASE_CURRENCY {
+            return Err(WalletError::FxRate(format!(
+                "{} is the base currency",
+                BASE_CURRENCY
+            )));
+        }
+        self.client.execute(
+            "INSERT INTO fx_rates (currency, rate_on, rate)
+             VALUES ($1, $2, $3::float8)
+             ON CONFLICT (currency, rate_on) DO UPDATE SET rate = EXCLUDED.rate",
+            &[&currency, &on, &rate],
+        )?;
+        outln!("1 {} = {:.4} {} on {}", currency, rate, BASE_CURRENCY, on);
+        Ok(())
+    }
+
+    pub(crate) fn list_fx_rates(&mut self, currency: Option<&str>) -> Result<(), WalletError> {
+        let currency = currency.map(parse_currency).transpose()?;
+        let rows = self.client.query(
+            "SELECT currency::text, rate_on, rate::float8 FROM fx_rates
+             WHERE $1::text IS NULL OR currency = $1
+             ORDER BY currency, rate_on DESC",
+            &[&currency],
+        )?;
+
+        outln!("\nExchange Rates ({} per unit):", BASE_CURRENCY);
+        outln!("{:<10} {:<12} {:<12}", "Currency", "Date", "Rate");
+        outln!("{:-<36}", "");
+        for row in rows.iter() {
+            let currency: String = row.get(0);
+            let rate_on: NaiveDate = row.get(1);
+            let rate: f64 = row.get(2);
+            outln!(
+                "{:<10} {:<12} {:<12.4}",
+                currency,
+                rate_on.to_string(),
+                rate
+            );
+        }
+        if rows.is_empty() {
+            outln!("No rates recorded. Add one with `fx set CURRENCY RATE`.");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use colored::Colorize;
+
+use crate::projection::{self, Completion};
+use crate::{dates, WalletDB, WalletError};
+
+// How far back the saving rate is measured
+const RATE_WINDOW_DAYS: i64 = 90;
+const BAR_WIDTH: usize = 20;
+
+fn progress_bar(fraction: f64) -> String {
+    let filled = ((fraction.clamp(0.0, 1.0) * BAR_WIDTH as f64).round()) as usize;
+    format!("[{}{}]", "#".repeat(filled), "-".repeat(BAR_WIDTH - filled))
+}
+
+impl WalletDB {
+    pub(crate) fn add_goal(
+        &mut self,
+        name: &str,
+        target: f64,
+        by: Option<NaiveDate>,
+        ledger_code: &str,
+    ) -> Result<(), WalletError> {
+        if target <= 0.0 {
+            return Err(WalletError::InvalidAmount(
+                "Goal target must be positive".to_string(),
+            ));
+        }
+        let ledger_id = self.retrieve_ledger_id(ledger_code)?;
+        self.client.execute(
+            "INSERT INTO goals (name, target, target_date, ledger_id) VALUES ($1, $2::float8, $3, $4)",
+            &[&name, &target, &by, &ledger_id],
+        )?;
+        match by {
+            Some(by) => outln!(
+                "Added goal: {} - {:.2} by {} in {}",
+                name,
+                target,
+                by,
+                ledger_code
+            ),
+            None => outln!("Added goal: {} - {:.2} in {}", name, target, ledger_code),
+        }
+        Ok(())
+    }
+
+    // Progress of every goal against its linked ledger's balance, with a
+    // projected completion date at the rate the ledger grew recently.
+    pub(crate) fn goal_status(&mut self) -> Result<(), WalletError> {
+        let today = dates::today();
+        let window_start = (today - Duration::days(RATE_WINDOW_DAYS))
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let rows = self.client.query(
+            "
+            SELECT g.name, g.target::float8, g.target_date, l.code,
+                   COALESCE(SUM(
+                       CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END -
+                       CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END
+                   ), 0)::float8 as balance,
+                   COALESCE(SUM(CASE WHEN p.created_at >= $1::timestamp THEN
+                       CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END -
+                       CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END
+                   ELSE 0 END), 0)::float8 as recent,
+                   MIN(p.created_at)::timestamp as first_at
+            FROM goals g
+            JOIN ledgers l ON l.id = g.ledger_id
+            LEFT JOIN proceedings p ON (p.cr_from = l.id OR p.db_to = l.id) AND NOT p.pending
+            GROUP BY g.id, g.name, g.target, g.target_date, l.code
+            ORDER BY g.target_date NULLS LAST, g.name
+            ",
+            &[&window_start],
+        )?;
+
+        outln!("\nGoal Status:");
+        if rows.is_empty() {
+            outln!("No goals yet. Add one with `goal add NAME TARGET --ledger CODE`.");
+            return Ok(());
+        }
+        for row in rows.iter() {
+            let name: String = row.get(0);
+            let target: f64 = row.get(1);
+            let deadline: Option<NaiveDate> = row.get(2);
+            let code: String = row.get(3);
+            let balance: f64 = row.get(4);
+            let recent: f64 = row.get(5);
+            let first_at: Option<NaiveDateTime> = row.get(6);
+
+            // A ledger younger than the window is measured over its own age
+            let days = first_at
+                .map(|first| (today - first.date()).num_days() + 1)
+                .unwrap_or(1)
+                .min(RATE_WINDOW_DAYS);
+            let rate = projection::daily_rate(recent, days);
+            let projection = projection::project(balance, target, rate, today, deadline);
+
+            outln!("{:-<75}", "");
+            outln!(
+                "{:<15} {} {:>5.1}%  {:.2} / {:.2} ({})",
+                name.bold(),
+                progress_bar(balance / target),
+                (balance / target * 100.0).max(0.0),
+                balance,
+                target,
+                code
+            );
+            let deadline_text = deadline
+                .map(|d| format!("by {}", d))
+                .unwrap_or_else(|| "no deadline".to_string());
+            let projected = match projection.completion {
+                Completion::Reached => "reached".green().to_string(),
+                Completion::On(day) => format!("projected {}", day),
+                Completion::Never => "no progress at the current rate".to_string(),
+            };
+            outln!(
+                "{:<15} {} | {:.2}/day over the last {} days | {}",
+                "",
+                deadline_text,
+                projection.daily_rate,
+                days,
+                projected
+            );
+            if !projection.on_track {
+                let warning = match (projection.required_rate, deadline) {
+                    (Some(_), Some(deadline)) if deadline < today => format!(
+                        "Off track: deadline passed, {:.2} still to go",
+                        target - balance
+                    ),
+                    (Some(required), Some(deadline)) => format!(
+                        "Off track: needs {:.2}/day to finish by {}",
+                        required, deadline
+                    ),
+                    _ => "Off track: the balance is not growing".to_string(),
+                };
+                outln!("{:<15} {}", "", warning.red());
+            }
+        }
+        outln!("{:-<75}", "");
+        Ok(())
+    }
+}
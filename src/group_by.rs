@@ -0,0 +1,151 @@
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+
+use crate::fx::{FxArgs, FxColumn};
+use crate::{footer, ReportPeriod, WalletDB, WalletError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    Day,
+    Week,
+    Month,
+    Ledger,
+    Kind,
+}
+
+impl GroupBy {
+    // Grouping key over the `p` proceedings / `x` postings of the report
+    // query. Fixed strings, so safe to format into the SQL.
+    fn key(&self) -> &'static str {
+        match self {
+            GroupBy::Day => "to_char(p.created_at, 'YYYY-MM-DD')",
+            GroupBy::Week => "to_char(date_trunc('week', p.created_at), 'YYYY-MM-DD')",
+            GroupBy::Month => "to_char(p.created_at, 'YYYY-MM')",
+            GroupBy::Ledger => "l.code",
+            GroupBy::Kind => "ledger_kind_at(l.id, p.created_at)",
+        }
+    }
+
+    fn heading(&self) -> &'static str {
+        match self {
+            GroupBy::Day => "Day",
+            GroupBy::Week => "Week Of",
+            GroupBy::Month => "Month",
+            GroupBy::Ledger => "Ledger",
+            GroupBy::Kind => "Kind",
+        }
+    }
+
+    // Periods read in date order; ledgers and kinds largest first
+    fn order(&self) -> &'static str {
+        match self {
+            GroupBy::Day | GroupBy::Week | GroupBy::Month => "key",
+            GroupBy::Ledger | GroupBy::Kind => "amount DESC, key",
+        }
+    }
+}
+
+// One row of a grouped report
+pub(crate) struct Group {
+    pub key: String,
+    pub entries: i64,
+    pub amount: f64,
+}
+
+// Renders any grouping as key, entry count and net amount, with a grand
+// total and the --also-in column. The caller adds the footer and fx footnote.
+fn render_grouped(title: &str, group_by: GroupBy, groups: &[Group], fx: &FxColumn) {
+    outln!("\n{}:", title);
+    outln!(
+        "{:<15} {:<10} {:<15}{}",
+        group_by.heading(),
+        "Entries",
+        "Net Amount",
+        fx.header()
+    );
+    outln!("{}", fx.rule(42));
+    for group in groups.iter() {
+        outln!(
+            "{:<15} {:<10} {:<15.2}{}",
+            group.key,
+            group.entries,
+            group.amount,
+            fx.cell(group.amount)
+        );
+    }
+    if groups.is_empty() {
+        outln!("No transactions in this period");
+    }
+    let total = groups.iter().fold(0.0, |sum, group| sum + group.amount);
+    outln!("{}", fx.rule(42));
+    outln!("{:<26} {:<15.2}{}", "Grand Total", total, fx.cell(total));
+}
+
+impl WalletDB {
+    // Net amounts between `start` and `end` on the same basis as the
+    // per-ledger spending report, grouped by `group_by`
+    pub(crate) fn grouped_totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+        group_by: GroupBy,
+    ) -> Result<Vec<Group>, WalletError> {
+        let query = format!(
+            "
+            SELECT {} as key,
+                   COUNT(DISTINCT p.id) as entries,
+                   SUM(x.amount)::float8 as amount
+            FROM proceedings p
+            CROSS JOIN LATERAL (VALUES
+                (p.db_to, p.amount),
+                (p.cr_from, CASE
+                    WHEN ledger_kind_at(p.cr_from, p.created_at) = 'LIABILITY' THEN -p.amount
+                    ELSE 0
+                END)
+            ) AS x(ledger_id, amount)
+            JOIN ledgers l ON l.id = x.ledger_id
+            WHERE p.created_at >= $1::timestamp
+                AND ($2::timestamp IS NULL OR p.created_at <= $2::timestamp)
+                AND NOT p.pending
+                AND x.amount != 0
+            GROUP BY key
+            ORDER BY {}
+            ",
+            group_by.key(),
+            group_by.order()
+        );
+        let rows = self.client.query(&query, &[&start, &end])?;
+        Ok(rows
+            .iter()
+            .map(|row| Group {
+                key: row.get(0),
+                entries: row.get(1),
+                amount: row.get(2),
+            })
+            .collect())
+    }
+
+    pub(crate) fn generate_grouped_report(
+        &mut self,
+        period: ReportPeriod,
+        group_by: GroupBy,
+        fx_args: &FxArgs,
+    ) -> Result<(), WalletError> {
+        let (start, end, period_str) = period.bounds()?;
+        let fx = self.fx_column(fx_args, start.date(), end.map(|end| end.date()))?;
+        let groups = self.grouped_totals(start, end, group_by)?;
+        let title = format!(
+            "Spending Report by {} ({})",
+            group_by.to_possible_value().unwrap().get_name(),
+            period_str
+        );
+        render_grouped(&title, group_by, &groups, &fx);
+        if let Some(spec) = self.report_footer.clone() {
+            let total = groups.iter().map(|group| group.amount).sum();
+            let vars = self.footer_vars(&period, total)?;
+            footer::print(&spec, 26, vars);
+        }
+        fx.footnote();
+        Ok(())
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+
+use crate::{dates, SpendEntry, WalletDB, WalletError};
+
+impl WalletDB {
+    // Bulk-loads a CSV statement with `COPY ... FROM STDIN` inside a single
+    // transaction. Rows are `date,patron,outlay,amount,narration`, optionally
+    // followed by `payee,project,tags` (tags separated by ';') for ledgers
+    // whose policies require them; a leading header row is skipped.
+    pub(crate) fn import_csv(&mut self, path: &str) -> Result<(), WalletError> {
+        let content = fs::read_to_string(path)?;
+        let mut entries = parse_entries(&content)?;
+        if entries.is_empty() {
+            outln!("Nothing to import from {}", path);
+            return Ok(());
+        }
+
+        self.ensure_months_unlocked(&entries)?;
+
+        let mut ledger_ids = HashMap::new();
+        for entry in entries.iter_mut() {
+            entry.validate().map_err(|e| match e {
+                WalletError::InvalidAmount(message) => WalletError::InvalidAmount(format!(
+                    "{}: {} ({})",
+                    message, entry.amount, entry.narration
+                )),
+                e => e,
+            })?;
+            let ids = [
+                self.cached_ledger_id(&mut ledger_ids, &entry.patron)?,
+                self.cached_ledger_id(&mut ledger_ids, &entry.outlay)?,
+            ];
+            self.apply_policies(ids, entry).map_err(|e| match e {
+                WalletError::PolicyViolation(message) => WalletError::PolicyViolation(format!(
+                    "{} for {} -> {} {} ({})",
+                    message, entry.patron, entry.outlay, entry.amount, entry.narration
+                )),
+                e => e,
+            })?;
+        }
+        let entries = self.skip_duplicates(entries)?;
+        if entries.is_empty() {
+            outln!("Nothing left to import from {}", path);
+            return Ok(());
+        }
+
+        let mut buffer = String::new();
+        for entry in entries.iter() {
+            let patron_id = self.cached_ledger_id(&mut ledger_ids, &entry.patron)?;
+            let outlay_id = self.cached_ledger_id(&mut ledger_ids, &entry.outlay)?;
+            let created_at = entry.created_at.unwrap_or_else(dates::now);
+            buffer.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                patron_id,
+                outlay_id,
+                entry.amount,
+                copy_escape(&entry.narration),
+                created_at.format("%Y-%m-%d %H:%M:%S"),
+                copy_nullable(&entry.payee),
+                copy_nullable(&entry.project),
+                copy_escape(&array_literal(&entry.tags))
+            ));
+        }
+
+        let mut transaction = self.client.transaction()?;
+        let mut writer = transaction.copy_in(
+            "COPY proceedings (cr_from, db_to, amount, narration, created_at, payee, project, tags)
+             FROM STDIN",
+        )?;
+        writer.write_all(buffer.as_bytes())?;
+        writer.finish()?;
+        transaction.commit()?;
+
+        outln!("Imported {} proceedings from {}", entries.len(), path);
+        Ok(())
+    }
+}
+
+fn parse_entries(content: &str) -> Result<Vec<SpendEntry>, WalletError> {
+    let today = dates::today();
+    let mut entries = Vec::new();
+    for (index, record) in parse_csv(content).into_iter().enumerate() {
+        let line = index + 1;
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        if index == 0 && record[0].trim().eq_ignore_ascii_case("date") {
+            continue;
+        }
+        let [date, patron, outlay, amount, narration, extra @ ..] = record.as_slice() else {
+            return Err(WalletError::Import(format!(
+                "record {}: expected at least 5 fields (date,patron,outlay,amount,narration), found {}",
+                line,
+                record.len()
+            )));
+        };
+        if extra.len() > 3 {
+            return Err(WalletError::Import(format!(
+                "record {}: expected at most 8 fields (..., payee,project,tags), found {}",
+                line,
+                record.len()
+            )));
+        }
+        let optional = |index: usize| {
+            extra
+                .get(index)
+                .map(|field| field.trim())
+                .filter(|field| !field.is_empty())
+                .map(str::to_string)
+        };
+        let day = dates::parse_day(date, today)
+            .map_err(|e| WalletError::Import(format!("record {}: {}", line, e)))?;
+        let amount = amount.trim().parse::<f64>().map_err(|_| {
+            WalletError::Import(format!("record {}: invalid amount '{}'", line, amount))
+        })?;
+        entries.push(SpendEntry {
+            patron: patron.trim().to_string(),
+            outlay: outlay.trim().to_string(),
+            amount,
+            narration: narration.trim().to_string(),
+            created_at: day.and_hms_opt(0, 0, 0),
+            payee: optional(0),
+            project: optional(1),
+            tags: optional(2)
+                .map(|tags| {
+                    tags.split(';')
+                        .map(str::trim)
+                        .filter(|tag| !tag.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        });
+    }
+    Ok(entries)
+}
+
+// Minimal RFC 4180 reader: comma separated, double-quoted fields may contain
+// commas, newlines and "" escapes.
+fn parse_csv(content: &str) -> Vec<Vec<String>> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = content.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => record.push(std::mem::take(&mut field)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            _ => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    records
+}
+
+// Escapes a value for COPY's text format
+fn copy_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+fn copy_nullable(value: &Option<String>) -> String {
+    value
+        .as_deref()
+        .map(copy_escape)
+        .unwrap_or_else(|| "\\N".to_string())
+}
+
+// A text[] literal with every element quoted, before COPY escaping
+fn array_literal(values: &[String]) -> String {
+    let elements: Vec<String> = values
+        .iter()
+        .map(|value| format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{{{}}}", elements.join(","))
+}
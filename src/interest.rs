@@ -0,0 +1,124 @@
+use chrono::{Datelike, Month, NaiveDate, NaiveDateTime};
+
+use crate::fx::{FxArgs, FxColumn};
+use crate::{dates, WalletDB, WalletError};
+
+// Start of `year` and start of the next, for half-open range queries
+pub(crate) fn year_range(year: i32) -> Result<(NaiveDateTime, NaiveDateTime), WalletError> {
+    let start = |year: i32| {
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .ok_or_else(|| WalletError::InvalidDate(format!("Invalid year: {}", year)))
+            .map(|day| day.and_hms_opt(0, 0, 0).unwrap())
+    };
+    Ok((start(year)?, start(year + 1)?))
+}
+
+impl WalletDB {
+    // Summarises what carrying debt cost over a year: every posting from a
+    // LIABILITY ledger into an INTEREST or FEE ledger, grouped by liability
+    // and month.
+    pub(crate) fn generate_interest_report(
+        &mut self,
+        year: Option<i32>,
+        fx_args: &FxArgs,
+    ) -> Result<(), WalletError> {
+        let year = year.unwrap_or_else(|| dates::today().year());
+        let (start, end) = year_range(year)?;
+        let fx = self.fx_column(fx_args, start.date(), end.date().pred_opt())?;
+
+        let query = "
+            SELECT
+                l.code,
+                l.name,
+                EXTRACT(MONTH FROM p.created_at)::int as month,
+                SUM(CASE WHEN ledger_kind_at(e.id, p.created_at) = 'INTEREST' THEN p.amount ELSE 0 END)::float8 as interest,
+                SUM(CASE WHEN ledger_kind_at(e.id, p.created_at) = 'FEE' THEN p.amount ELSE 0 END)::float8 as fees
+            FROM proceedings p
+            JOIN ledgers l ON l.id = p.cr_from
+            JOIN ledgers e ON e.id = p.db_to
+            WHERE p.created_at >= $1::timestamp AND p.created_at < $2::timestamp AND NOT p.pending
+                AND ledger_kind_at(l.id, p.created_at) = 'LIABILITY'
+                AND ledger_kind_at(e.id, p.created_at) IN ('INTEREST', 'FEE')
+            GROUP BY l.code, l.name, month
+            ORDER BY l.code, month
+        ";
+        let rows = self.client.query(query, &[&start, &end])?;
+
+        outln!("\nInterest & Fees Report ({}):", year);
+        outln!(
+            "{:<10} {:<30} {:<10} {:<15} {:<15} {:<15}{}",
+            "Code",
+            "Name",
+            "Month",
+            "Interest",
+            "Fees",
+            "Total",
+            fx.header()
+        );
+        outln!("{}", fx.rule(95));
+
+        let mut current: Option<String> = None;
+        let (mut ledger_interest, mut ledger_fees) = (0.0, 0.0);
+        let (mut total_interest, mut total_fees) = (0.0, 0.0);
+        for row in rows.iter() {
+            let code: String = row.get(0);
+            let name: String = row.get(1);
+            let month: i32 = row.get(2);
+            let interest: f64 = row.get(3);
+            let fees: f64 = row.get(4);
+
+            if current.as_deref() != Some(code.as_str()) {
+                if let Some(previous) = current.take() {
+                    print_ledger_subtotal(&previous, ledger_interest, ledger_fees, &fx);
+                }
+                current = Some(code.clone());
+                ledger_interest = 0.0;
+                ledger_fees = 0.0;
+            }
+            ledger_interest += interest;
+            ledger_fees += fees;
+            total_interest += interest;
+            total_fees += fees;
+
+            let month_name = Month::try_from(month as u8)
+                .map(|m| m.name().to_string())
+                .unwrap_or_default();
+            outln!(
+                "{:<10} {:<30} {:<10} {:<15.2} {:<15.2} {:<15.2}{}",
+                code,
+                name,
+                month_name,
+                interest,
+                fees,
+                interest + fees,
+                fx.cell(interest + fees)
+            );
+        }
+        if let Some(previous) = current {
+            print_ledger_subtotal(&previous, ledger_interest, ledger_fees, &fx);
+        }
+
+        outln!("{}", fx.rule(95));
+        outln!(
+            "{:<52} {:<15.2} {:<15.2} {:<15.2}{}",
+            "Cost of Debt",
+            total_interest,
+            total_fees,
+            total_interest + total_fees,
+            fx.cell(total_interest + total_fees)
+        );
+        fx.footnote();
+        Ok(())
+    }
+}
+
+fn print_ledger_subtotal(code: &str, interest: f64, fees: f64, fx: &FxColumn) {
+    outln!(
+        "{:<52} {:<15.2} {:<15.2} {:<15.2}{}",
+        format!("{} Total", code),
+        interest,
+        fees,
+        interest + fees,
+        fx.cell(interest + fees)
+    );
+}
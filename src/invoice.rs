@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use chrono::{Duration, NaiveDate};
+use colored::Colorize;
+
+use crate::{WalletDB, WalletError};
+
+// Due date of an invoice added without --due
+const PAYMENT_TERMS_DAYS: i64 = 30;
+
+const BUCKETS: [&str; 5] = ["Current", "1-30", "31-60", "61-90", "90+"];
+
+// Aging bucket of an amount `days_overdue` past its due date
+fn bucket(days_overdue: i64) -> usize {
+    match days_overdue {
+        i64::MIN..=0 => 0,
+        1..=30 => 1,
+        31..=60 => 2,
+        61..=90 => 3,
+        _ => 4,
+    }
+}
+
+impl WalletDB {
+    pub(crate) fn add_invoice(
+        &mut self,
+        client: &str,
+        amount: f64,
+        due: Option<NaiveDate>,
+        issued: NaiveDate,
+        note: Option<&str>,
+    ) -> Result<(), WalletError> {
+        if amount <= 0.0 {
+            return Err(WalletError::InvalidAmount(
+                "Invoice amount must be positive".to_string(),
+            ));
+        }
+        let due = due.unwrap_or(issued + Duration::days(PAYMENT_TERMS_DAYS));
+        if due < issued {
+            return Err(WalletError::DateRangeError(format!(
+                "Invoice due {} before it was issued on {}",
+                due, issued
+            )));
+        }
+        let ledger_id = self.retrieve_ledger_id(client)?;
+        let id: i32 = self
+            .client
+            .query_one(
+                "INSERT INTO invoices (ledger_id, amount, issued_on, due_on, note)
+                 VALUES ($1, $2::float8, $3, $4, $5) RETURNING id",
+                &[&ledger_id, &amount, &issued, &due, &note],
+            )?
+            .get(0);
+        outln!(
+            "Added invoice #{} for {}: {:.2} due {}",
+            id,
+            client,
+            amount,
+            due
+        );
+        Ok(())
+    }
+
+    // Settles all or part of an invoice with a payment received from the
+    // client, i.e. a transaction crediting the invoice's client ledger
+    pub(crate) fn link_payment(
+        &mut self,
+        invoice_id: i32,
+        proceeding_id: i32,
+        amount: Option<f64>,
+    ) -> Result<(), WalletError> {
+        let invoice = self
+            .client
+            .query_opt(
+                "SELECT i.ledger_id, l.code, i.amount::float8,
+                        (i.amount - COALESCE((SELECT SUM(amount) FROM invoice_payments
+                                              WHERE invoice_id = i.id), 0))::float8
+                 FROM invoices i
+                 JOIN ledgers l ON l.id = i.ledger_id
+                 WHERE i.id = $1",
+                &[&invoice_id],
+            )?
+            .ok_or_else(|| WalletError::Invoice(format!("no invoice #{}", invoice_id)))?;
+        let ledger_id: i32 = invoice.get(0);
+        let client: String = invoice.get(1);
+        let outstanding: f64 = invoice.get(3);
+
+        let payment = self
+            .client
+            .query_opt(
+                "SELECT p.cr_from,
+                        (p.amount - COALESCE((SELECT SUM(amount) FROM invoice_payments
+                                              WHERE proceeding_id = p.id), 0))::float8
+                 FROM proceedings p
+                 WHERE p.id = $1",
+                &[&proceeding_id],
+            )?
+            .ok_or(WalletError::TransactionNotFound(proceeding_id))?;
+        let cr_from: i32 = payment.get(0);
+        let unallocated: f64 = payment.get(1);
+        if cr_from != ledger_id {
+            return Err(WalletError::Invoice(format!(
+                "transaction #{} is not a payment from {}",
+                proceeding_id, client
+            )));
+        }
+
+        let amount = amount.unwrap_or(unallocated.min(outstanding));
+        if amount <= 0.0 {
+            return Err(WalletError::InvalidAmount(format!(
+                "nothing to link: invoice #{} has {:.2} outstanding and transaction #{} {:.2} unallocated",
+                invoice_id, outstanding, proceeding_id, unallocated
+            )));
+        }
+        if amount > outstanding + 0.005 || amount > unallocated + 0.005 {
+            return Err(WalletError::InvalidAmount(format!(
+                "{:.2} exceeds the {:.2} outstanding on invoice #{} or the {:.2} unallocated on transaction #{}",
+                amount, outstanding, invoice_id, unallocated, proceeding_id
+            )));
+        }
+
+        self.client.execute(
+            "INSERT INTO invoice_payments (invoice_id, proceeding_id, amount)
+             VALUES ($1, $2, $3::float8)
+             ON CONFLICT (invoice_id, proceeding_id)
+             DO UPDATE SET amount = invoice_payments.amount + EXCLUDED.amount",
+            &[&invoice_id, &proceeding_id, &amount],
+        )?;
+        let remaining = outstanding - amount;
+        outln!(
+            "Linked {:.2} of transaction #{} to invoice #{}",
+            amount,
+            proceeding_id,
+            invoice_id
+        );
+        if remaining < 0.005 {
+            outln!(
+                "{}",
+                format!("Invoice #{} is paid in full", invoice_id).green()
+            );
+        } else {
+            outln!("{:.2} still outstanding", remaining);
+        }
+        Ok(())
+    }
+
+    // Accounts receivable as of a day: every unpaid invoice and what each
+    // client owes, bucketed by how long it has been overdue
+    pub(crate) fn invoice_aging(&mut self, as_of: NaiveDate) -> Result<(), WalletError> {
+        let cutoff = as_of.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let rows = self.client.query(
+            "
+            SELECT i.id, l.code, i.issued_on, i.due_on, i.amount::float8,
+                   COALESCE((SELECT SUM(ip.amount) FROM invoice_payments ip
+                             JOIN proceedings p ON p.id = ip.proceeding_id
+                             WHERE ip.invoice_id = i.id AND p.created_at < $2::timestamp), 0)::float8 AS paid
+            FROM invoices i
+            JOIN ledgers l ON l.id = i.ledger_id
+            WHERE i.issued_on <= $1
+            ORDER BY l.code, i.due_on, i.id
+            ",
+            &[&as_of, &cutoff],
+        )?;
+
+        outln!("\nAccounts Receivable Aging as of {}:", as_of);
+        outln!(
+            "{:<6} {:<10} {:<12} {:<12} {:<12} {:<12} {:<12} {:<8}",
+            "Id",
+            "Client",
+            "Issued",
+            "Due",
+            "Amount",
+            "Paid",
+            "Outstanding",
+            "Overdue"
+        );
+        outln!("{:-<90}", "");
+        let mut clients: BTreeMap<String, [f64; 5]> = BTreeMap::new();
+        for row in rows.iter() {
+            let amount: f64 = row.get(4);
+            let paid: f64 = row.get(5);
+            let outstanding = amount - paid;
+            if outstanding < 0.005 {
+                continue;
+            }
+            let id: i32 = row.get(0);
+            let client: String = row.get(1);
+            let issued: NaiveDate = row.get(2);
+            let due: NaiveDate = row.get(3);
+            let days_overdue = (as_of - due).num_days();
+
+            let overdue = if days_overdue > 0 {
+                format!("{}d", days_overdue).red().to_string()
+            } else {
+                "-".to_string()
+            };
+            outln!(
+                "{:<6} {:<10} {:<12} {:<12} {:<12.2} {:<12.2} {:<12.2} {}",
+                id,
+                client,
+                issued.to_string(),
+                due.to_string(),
+                amount,
+                paid,
+                outstanding,
+                overdue
+            );
+            clients.entry(client).or_insert([0.0; 5])[bucket(days_overdue)] += outstanding;
+        }
+        if clients.is_empty() {
+            outln!("No outstanding invoices");
+            return Ok(());
+        }
+
+        outln!(
+            "\n{:<10} {:<12} {:<12} {:<12} {:<12} {:<12} {:<12}",
+            "Client",
+            BUCKETS[0],
+            BUCKETS[1],
+            BUCKETS[2],
+            BUCKETS[3],
+            BUCKETS[4],
+            "Total"
+        );
+        outln!("{:-<88}", "");
+        let mut totals = [0.0; 5];
+        for (client, buckets) in clients.iter() {
+            for (total, amount) in totals.iter_mut().zip(buckets) {
+                *total += amount;
+            }
+            outln!(
+                "{:<10} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2}",
+                client,
+                buckets[0],
+                buckets[1],
+                buckets[2],
+                buckets[3],
+                buckets[4],
+                buckets.iter().sum::<f64>()
+            );
+        }
+        outln!("{:-<88}", "");
+        outln!(
+            "{:<10} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2}",
+            "Total",
+            totals[0],
+            totals[1],
+            totals[2],
+            totals[3],
+            totals[4],
+            totals.iter().sum::<f64>()
+        );
+        Ok(())
+    }
+}
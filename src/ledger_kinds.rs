@@ -0,0 +1,71 @@
+use chrono::NaiveDate;
+
+use crate::{WalletDB, WalletError};
+
+// Common table expression giving each ledger's kind history as spans from
+// effective_from up to the next change, for reports that join proceedings to
+// the kind their ledgers had at the time in one pass rather than calling
+// ledger_kind_at per row. Ledgers without history fall back to ledgers.kind.
+pub(crate) const KIND_SPANS: &str = "
+    kind_spans AS (
+        SELECT ledger_id, kind, effective_from,
+               LEAD(effective_from) OVER (PARTITION BY ledger_id ORDER BY effective_from)
+                   AS effective_to
+        FROM ledger_kinds
+    )";
+
+impl WalletDB {
+    // Records that `code` has kind `kind` from `effective` onwards. The first
+    // rekind also stores the original kind from the epoch so transactions
+    // before the change keep their meaning in reports (see ledger_kind_at).
+    pub(crate) fn rekind_ledger(
+        &mut self,
+        code: &str,
+        kind: &str,
+        effective: NaiveDate,
+    ) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+        let kind = kind.to_uppercase();
+
+        let mut transaction = self.client.transaction()?;
+        transaction.execute(
+            "INSERT INTO ledger_kinds (ledger_id, kind, effective_from)
+             SELECT id, kind, DATE '1970-01-01' FROM ledgers
+             WHERE id = $1
+               AND NOT EXISTS (SELECT 1 FROM ledger_kinds WHERE ledger_id = $1)",
+            &[&ledger_id],
+        )?;
+        transaction.execute(
+            "INSERT INTO ledger_kinds (ledger_id, kind, effective_from) VALUES ($1, $2, $3)
+             ON CONFLICT (ledger_id, effective_from) DO UPDATE SET kind = EXCLUDED.kind",
+            &[&ledger_id, &kind, &effective],
+        )?;
+        // ledgers.kind always holds the kind in effect today
+        transaction.execute(
+            "UPDATE ledgers
+             SET kind = ledger_kind_at(id, LOCALTIMESTAMP), updated_at = CURRENT_TIMESTAMP
+             WHERE id = $1",
+            &[&ledger_id],
+        )?;
+        transaction.commit()?;
+
+        let rows = self.client.query(
+            "SELECT effective_from, kind FROM ledger_kinds
+             WHERE ledger_id = $1 ORDER BY effective_from",
+            &[&ledger_id],
+        )?;
+        outln!("\nKind History for {}:", code);
+        outln!("{:<15} {:<20}", "Effective", "Kind");
+        outln!("{:-<35}", "");
+        for row in rows.iter() {
+            let effective_from: NaiveDate = row.get(0);
+            let kind: String = row.get(1);
+            outln!(
+                "{:<15} {:<20}",
+                effective_from.format("%Y-%m-%d").to_string(),
+                kind
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use chrono::NaiveDateTime;
+use clap::ValueEnum;
+
+use crate::{WalletDB, WalletError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum LinkKind {
+    // Expense paid out and the reimbursement that came back for it
+    Reimbursement,
+    // The two legs of a move between accounts
+    Transfer,
+    // Purchase and its refund
+    Refund,
+    // Loan disbursement and an instalment paying it back
+    Emi,
+}
+
+impl LinkKind {
+    fn name(&self) -> &'static str {
+        match self {
+            LinkKind::Reimbursement => "reimbursement",
+            LinkKind::Transfer => "transfer",
+            LinkKind::Refund => "refund",
+            LinkKind::Emi => "emi",
+        }
+    }
+}
+
+// A proceeding as it appears in a trace
+struct Step {
+    created_at: NaiveDateTime,
+    from: String,
+    to: String,
+    amount: f64,
+    narration: String,
+}
+
+// Linked ids by the proceeding they hang off, with the link's kind
+type Edges = HashMap<i32, Vec<(i32, String)>>;
+
+// Links reachable from $1 following from_id -> to_id. UNION drops repeated
+// rows, so a cycle of links ends the recursion instead of looping.
+const DOWNSTREAM: &str = "
+    WITH RECURSIVE chain(from_id, to_id, kind) AS (
+        SELECT from_id, to_id, kind FROM links WHERE from_id = $1
+        UNION
+        SELECT l.from_id, l.to_id, l.kind FROM links l JOIN chain c ON l.from_id = c.to_id
+    )
+    SELECT from_id, to_id, kind FROM chain ORDER BY from_id, to_id
+";
+// The same walk against the direction of the links
+const UPSTREAM: &str = "
+    WITH RECURSIVE chain(from_id, to_id, kind) AS (
+        SELECT from_id, to_id, kind FROM links WHERE to_id = $1
+        UNION
+        SELECT l.from_id, l.to_id, l.kind FROM links l JOIN chain c ON l.to_id = c.from_id
+    )
+    SELECT from_id, to_id, kind FROM chain ORDER BY to_id, from_id
+";
+
+impl WalletDB {
+    fn ensure_proceeding(&mut self, id: i32) -> Result<(), WalletError> {
+        self.client
+            .query_opt("SELECT 1 FROM proceedings WHERE id = $1", &[&id])?
+            .ok_or(WalletError::TransactionNotFound(id))?;
+        Ok(())
+    }
+
+    // Records that money from proceeding `from` moved on in proceeding `to`.
+    // Linking a pair again replaces the kind.
+    pub(crate) fn link(&mut self, from: i32, to: i32, kind: LinkKind) -> Result<(), WalletError> {
+        if from == to {
+            return Err(WalletError::Link(format!(
+                "#{} cannot be linked to itself",
+                from
+            )));
+        }
+        self.ensure_proceeding(from)?;
+        self.ensure_proceeding(to)?;
+        self.client.execute(
+            "INSERT INTO links (from_id, to_id, kind) VALUES ($1, $2, $3)
+             ON CONFLICT (from_id, to_id) DO UPDATE SET kind = EXCLUDED.kind",
+            &[&from, &to, &kind.name()],
+        )?;
+        outln!("Linked #{} -> #{} ({})", from, to, kind.name());
+        Ok(())
+    }
+
+    pub(crate) fn unlink(&mut self, from: i32, to: i32) -> Result<(), WalletError> {
+        let removed = self.client.execute(
+            "DELETE FROM links WHERE from_id = $1 AND to_id = $2",
+            &[&from, &to],
+        )?;
+        if removed == 0 {
+            return Err(WalletError::Link(format!(
+                "#{} is not linked to #{}",
+                from, to
+            )));
+        }
+        outln!("Unlinked #{} -> #{}", from, to);
+        Ok(())
+    }
+
+    fn link_edges(&mut self, query: &str, id: i32) -> Result<Vec<(i32, i32, String)>, WalletError> {
+        let rows = self.client.query(query, &[&id])?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect())
+    }
+
+    // Prints where proceeding `id`'s money came from and the chain of linked
+    // proceedings it went on through, ending with the ledgers it reached
+    pub(crate) fn trace(&mut self, id: i32) -> Result<(), WalletError> {
+        self.ensure_proceeding(id)?;
+        let downstream = self.link_edges(DOWNSTREAM, id)?;
+        let upstream = self.link_edges(UPSTREAM, id)?;
+
+        let mut ids: Vec<i32> = vec![id];
+        for (from, to, _) in downstream.iter().chain(upstream.iter()) {
+            ids.push(*from);
+            ids.push(*to);
+        }
+        let rows = self.client.query(
+            "SELECT p.id, p.created_at::timestamp, f.code, t.code, p.amount::float8, p.narration
+             FROM proceedings p
+             JOIN ledgers f ON f.id = p.cr_from
+             JOIN ledgers t ON t.id = p.db_to
+             WHERE p.id = ANY($1)",
+            &[&ids],
+        )?;
+        let steps: HashMap<i32, Step> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.get(0),
+                    Step {
+                        created_at: row.get(1),
+                        from: row.get(2),
+                        to: row.get(3),
+                        amount: row.get(4),
+                        narration: row.get(5),
+                    },
+                )
+            })
+            .collect();
+
+        let mut forward: Edges = HashMap::new();
+        for (from, to, kind) in downstream.iter() {
+            forward.entry(*from).or_default().push((*to, kind.clone()));
+        }
+        let mut backward: Edges = HashMap::new();
+        for (from, to, kind) in upstream.iter() {
+            backward.entry(*to).or_default().push((*from, kind.clone()));
+        }
+
+        outln!("\nTrace of transaction #{}", id);
+        outln!("{:-<86}", "");
+        if !backward.is_empty() {
+            outln!("Came from:");
+            print_chain(id, 1, "<-", &backward, &steps, &mut HashSet::from([id]));
+            outln!("");
+        }
+        outln!("{}", describe(id, &steps));
+        if forward.is_empty() {
+            outln!("{:-<86}", "");
+            outln!("No onward links.");
+            return Ok(());
+        }
+        print_chain(id, 1, "->", &forward, &steps, &mut HashSet::from([id]));
+        outln!("{:-<86}", "");
+
+        // Proceedings with nothing linked after them are where the money
+        // ended up, totalled by the ledger they went to
+        let leaves: BTreeSet<i32> = downstream
+            .iter()
+            .map(|(_, to, _)| *to)
+            .filter(|to| !forward.contains_key(to))
+            .collect();
+        let mut ended: BTreeMap<&str, f64> = BTreeMap::new();
+        for step in leaves.iter().filter_map(|leaf| steps.get(leaf)) {
+            *ended.entry(step.to.as_str()).or_default() += step.amount;
+        }
+        outln!("Ended up in:");
+        for (ledger, amount) in ended {
+            outln!("  {:<10} {:>12.2}", ledger, amount);
+        }
+        Ok(())
+    }
+}
+
+fn describe(id: i32, steps: &HashMap<i32, Step>) -> String {
+    match steps.get(&id) {
+        Some(step) => format!(
+            "#{:<6} {} {:<10} -> {:<10} {:>12.2}  {}",
+            id,
+            step.created_at.format("%Y-%m-%d"),
+            step.from,
+            step.to,
+            step.amount,
+            step.narration
+        ),
+        None => format!("#{}", id),
+    }
+}
+
+// Prints the proceedings linked to `id` along `edges`, one level of indent
+// per link. A proceeding reached twice is only expanded the first time.
+fn print_chain(
+    id: i32,
+    depth: usize,
+    arrow: &str,
+    edges: &Edges,
+    steps: &HashMap<i32, Step>,
+    seen: &mut HashSet<i32>,
+) {
+    for (next, kind) in edges.get(&id).into_iter().flatten() {
+        let repeat = !seen.insert(*next);
+        outln!(
+            "{}{} {:<14} {}{}",
+            "  ".repeat(depth),
+            arrow,
+            kind,
+            describe(*next, steps),
+            if repeat { " (see above)" } else { "" }
+        );
+        if !repeat {
+            print_chain(*next, depth + 1, arrow, edges, steps, seen);
+        }
+    }
+}
@@ -1,15 +1,116 @@
-use chrono::{
-    DateTime, Datelike, Duration, Month, NaiveDate, NaiveDateTime, ParseError, Timelike, Utc,
-};
-use clap::{Parser, Subcommand};
+use chrono::{Datelike, Duration, Month, NaiveDate, NaiveDateTime, ParseError};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
 use colored::Colorize;
-use dialoguer::{theme::ColorfulTheme, Confirm};
-use postgres::{Client, Error as PgError, NoTls};
+use postgres::error::SqlState;
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::types::ToSql;
+use postgres::Error as PgError;
+use std::collections::{BTreeMap, HashMap};
+use std::process::ExitCode;
 use thiserror::Error; // Add colored for colored output
 
+#[macro_use]
+mod output;
+
+mod budget;
+mod card;
+mod completions;
+mod config;
+mod dashboard;
+mod dates;
+mod dedup;
+mod demo;
+mod footer;
+mod forget;
+mod fx;
+mod goal;
+mod group_by;
+mod import;
+mod interest;
+mod invoice;
+mod json;
+mod ledger_kinds;
+mod links;
+mod paging;
+mod pdf;
+mod pending;
+mod policy;
+mod pool;
+mod profile;
+mod projection;
+mod raster;
+mod reconcile;
+mod review;
+mod serve;
+mod show;
+mod snapshot;
+mod spend_templates;
+mod statement;
+mod store;
+mod sum;
+mod tax;
+mod template;
+mod upgrade;
+
+use completions::Shell;
+use config::Config;
+use fx::FxArgs;
+use group_by::GroupBy;
+use ledger_kinds::KIND_SPANS;
+use links::LinkKind;
+use paging::PageArgs;
+use pool::{Pool, PooledClient};
+use review::ReviewFormat;
+use statement::StatementFormat;
+
 // WalletDB struct to manage database connection
 struct WalletDB {
-    client: Client,
+    client: PooledClient,
+    pool: Pool,
+    dedup_window: Duration,
+    report_footer: Option<String>,
+}
+
+// A single spend to record, as accepted by proceed_spend_batch and the importer
+#[derive(Clone, Debug, Default)]
+struct SpendEntry {
+    patron: String,
+    outlay: String,
+    amount: f64,
+    narration: String,
+    created_at: Option<NaiveDateTime>,
+    // Pending entries (post-dated cheques, card authorisations) stay out of
+    // balances until confirmed with clear-item
+    pending: bool,
+    clears_on: Option<NaiveDate>,
+    payee: Option<String>,
+    project: Option<String>,
+    tags: Vec<String>,
+    // Tax included in `amount`, and the rate it was worked out from
+    tax_amount: Option<f64>,
+    tax_rate: Option<f64>,
+    // When it was matched against a bank statement by `reconcile`
+    cleared_at: Option<NaiveDateTime>,
+}
+
+impl SpendEntry {
+    // Checks that hold whichever store the entry goes to
+    fn validate(&self) -> Result<(), WalletError> {
+        if self.amount <= 0.0 {
+            return Err(WalletError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+        if let Some(tax) = self.tax_amount {
+            if tax < 0.0 || tax > self.amount {
+                return Err(WalletError::InvalidAmount(format!(
+                    "Tax {:.2} must be between 0 and the amount {:.2}",
+                    tax, self.amount
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
@@ -30,6 +131,68 @@ pub enum WalletError {
     InvalidMonth(String),
     #[error("Invalid cap: {0}")]
     InvalidCap(String),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Import error: {0}")]
+    Import(String),
+    #[error("Period locked: {0}")]
+    PeriodLocked(String),
+    #[error("Prompt error: {0}")]
+    Prompt(#[from] dialoguer::Error),
+    #[error("Transaction not found: {0}")]
+    TransactionNotFound(i32),
+    #[error("Upgrade error: {0}")]
+    Upgrade(String),
+    #[error("No snapshot: {0}")]
+    NoSnapshot(String),
+    #[error("Policy violation: {0}")]
+    PolicyViolation(String),
+    #[error("Invoice error: {0}")]
+    Invoice(String),
+    #[error("Template not found: {0}")]
+    TemplateNotFound(String),
+    #[error("Exchange rate error: {0}")]
+    FxRate(String),
+    #[error("Profile error: {0}")]
+    Profile(String),
+    #[error("Time zone error: {0}")]
+    Timezone(String),
+    #[error("Ledger already exists: {0}")]
+    LedgerExists(String),
+    #[error("Link error: {0}")]
+    Link(String),
+}
+
+impl WalletError {
+    // Process exit status for this error, distinct per variant so scripts can
+    // tell failures apart (2 is left to clap for usage errors)
+    fn exit_code(&self) -> u8 {
+        match self {
+            WalletError::Database(_) => 3,
+            WalletError::InvalidAmount(_) => 4,
+            WalletError::LedgerNotFound(_) => 5,
+            WalletError::ParseError(_) => 6,
+            WalletError::InvalidDate(_) => 7,
+            WalletError::DateRangeError(_) => 8,
+            WalletError::InvalidMonth(_) => 9,
+            WalletError::InvalidCap(_) => 10,
+            WalletError::Io(_) => 11,
+            WalletError::Import(_) => 12,
+            WalletError::PeriodLocked(_) => 13,
+            WalletError::Prompt(_) => 14,
+            WalletError::TransactionNotFound(_) => 15,
+            WalletError::Upgrade(_) => 16,
+            WalletError::NoSnapshot(_) => 17,
+            WalletError::PolicyViolation(_) => 18,
+            WalletError::Invoice(_) => 19,
+            WalletError::TemplateNotFound(_) => 20,
+            WalletError::FxRate(_) => 21,
+            WalletError::Profile(_) => 22,
+            WalletError::Timezone(_) => 23,
+            WalletError::LedgerExists(_) => 24,
+            WalletError::Link(_) => 25,
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -58,17 +221,162 @@ impl clap::ValueEnum for ReportPeriod {
     }
 }
 
+impl ReportPeriod {
+    // The period picked by a report's positional period or its --date or
+    // --from/--to flags. `command` is how the report is invoked (e.g.
+    // "ledger-report <code>"), for the error messages.
+    fn from_args(
+        command: &str,
+        period: Option<ReportPeriod>,
+        date: Option<String>,
+        from: Option<String>,
+        to: Option<String>,
+    ) -> Result<ReportPeriod, WalletError> {
+        match (period, date, from, to) {
+            (Some(p), None, None, None) => Ok(p),
+            (None, Some(date), None, None) => Ok(ReportPeriod::Date(date)),
+            (None, None, Some(from), Some(to)) => Ok(ReportPeriod::FromTo { from, to }),
+            (None, None, None, None) => Ok(ReportPeriod::All), // Default to All if nothing is specified
+            (Some(_), Some(_), _, _) => {
+                Err(WalletError::InvalidDate(format!(
+                    "Cannot specify both a period and a date. Use either 'spendlog {0} <period>' or 'spendlog {0} --date <DATE>'.",
+                    command
+                )))
+            }
+            (Some(_), _, Some(_), Some(_)) => {
+                Err(WalletError::InvalidDate(format!(
+                    "Cannot specify both a period and a date range. Use either 'spendlog {0} <period>' or 'spendlog {0} --from <DATE> --to <DATE>'.",
+                    command
+                )))
+            }
+            (None, None, Some(_), None) | (None, None, None, Some(_)) => {
+                Err(WalletError::InvalidDate(
+                    "Must specify both --from and --to dates for a date range.".to_string(),
+                ))
+            }
+            _ => {
+                Err(WalletError::InvalidDate(format!(
+                    "Invalid combination of arguments. Use 'spendlog {0} <period>', 'spendlog {0} --date <DATE>', or 'spendlog {0} --from <DATE> --to <DATE>'.",
+                    command
+                )))
+            }
+        }
+    }
+
+    // Resolves the period into its start, optional end and a label for the
+    // report header. Explicit dates go through the dates module so every
+    // report accepts the same forms ("yesterday", "apr 15", ...). Bounds are
+    // wall-clock times in the user's time zone, as the database compares them.
+    fn bounds(&self) -> Result<(NaiveDateTime, Option<NaiveDateTime>, String), WalletError> {
+        let today = dates::today();
+        let midnight = |day: NaiveDate| day.and_hms_opt(0, 0, 0).unwrap();
+        match self {
+            ReportPeriod::Today => Ok((midnight(today), None, "Today".to_string())),
+            ReportPeriod::Week => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                Ok((midnight(monday), None, "This Week".to_string()))
+            }
+            ReportPeriod::Month => Ok((
+                midnight(today.with_day(1).unwrap()),
+                None,
+                "This Month".to_string(),
+            )),
+            ReportPeriod::All => {
+                let start =
+                    NaiveDateTime::parse_from_str("1970-01-01 00:00:00", "%Y-%m-%d %H:%M:%S")?;
+                Ok((start, None, "All Time".to_string()))
+            }
+            ReportPeriod::Date(date_str) => {
+                let (first, last) = dates::parse_span(date_str, today)?;
+                let label = if first == last {
+                    format!("Date: {}", first)
+                } else {
+                    format!("Date: {} to {}", first, last)
+                };
+                Ok((
+                    first.and_hms_opt(0, 0, 0).unwrap(),
+                    Some(last.and_hms_opt(23, 59, 59).unwrap()),
+                    label,
+                ))
+            }
+            ReportPeriod::FromTo { from, to } => {
+                let (from_date, _) = dates::parse_span(from, today)?;
+                let (_, to_date) = dates::parse_span(to, today)?;
+                if from_date > to_date {
+                    return Err(WalletError::DateRangeError(
+                        "The 'from' date must be earlier than or equal to the 'to' date."
+                            .to_string(),
+                    ));
+                }
+                let start = from_date.and_hms_opt(0, 0, 0).unwrap();
+                let end = to_date.and_hms_opt(23, 59, 59).unwrap();
+                Ok((
+                    start,
+                    Some(end),
+                    format!("From {} to {}", from_date, to_date),
+                ))
+            }
+        }
+    }
+}
+
 impl WalletDB {
-    fn new() -> Result<Self, WalletError> {
-        // Connect to PostgreSQL
-        let client = Client::connect(
-            "host=localhost user=postgres password=postgres dbname=wallet_db",
-            NoTls,
-        )?;
+    fn new(
+        profile: Option<&str>,
+        low_memory: bool,
+        timezone: Option<&str>,
+    ) -> Result<Self, WalletError> {
+        // Connect to PostgreSQL through the pool, in the profile's schema and
+        // the user's time zone. Low-memory mode holds a single connection,
+        // which also makes the dashboard build its panels one after another.
+        let config = Config::load();
+        let timezone = timezone.or(config.timezone.as_deref());
+        let profile = profile
+            .or(config.profile.as_deref())
+            .unwrap_or(profile::DEFAULT_PROFILE);
+        let pool_size = if low_memory || config.low_memory {
+            1
+        } else {
+            config.pool_size
+        };
+        let pool = Pool::new(
+            &config.database_url,
+            pool_size,
+            &profile::schema_for(profile)?,
+            timezone,
+        );
+        let mut db = WalletDB::from_pool(&pool).map_err(|e| match (e, timezone) {
+            (WalletError::Database(e), Some(zone))
+                if e.code() == Some(&SqlState::INVALID_PARAMETER_VALUE) =>
+            {
+                WalletError::Timezone(format!(
+                    "unknown time zone '{}'; use a name such as Asia/Kolkata or UTC",
+                    zone
+                ))
+            }
+            (e, _) => e,
+        })?;
+        db.dedup_window = config.dedup_window;
+        db.report_footer = config.report_footer.clone();
 
-        // Create tables if they don't exist
+        // Day boundaries computed here follow the session's zone
+        let zone = match timezone {
+            Some(zone) => zone.to_string(),
+            None => db.client.query_one("SHOW TimeZone", &[])?.get(0),
+        };
+        dates::set_zone(&zone);
+        Ok(db)
+    }
 
-        Ok(WalletDB { client })
+    // A handle over another connection from the same pool, for work that
+    // runs alongside this one (see dashboard)
+    fn from_pool(pool: &Pool) -> Result<Self, WalletError> {
+        Ok(WalletDB {
+            client: pool.get()?,
+            pool: pool.clone(),
+            dedup_window: Duration::zero(),
+            report_footer: None,
+        })
     }
 
     fn add_ledger(
@@ -83,247 +391,214 @@ impl WalletDB {
             "INSERT INTO ledgers (code, name, description, sort, kind) VALUES ($1, $2, $3, $4, $5)",
             &[&code, &name, &description, &sort, &kind],
         )?;
-        println!("Added ledger: {} - {}", code, name);
+        outln!("Added ledger: {} - {}", code, name);
         Ok(())
     }
 
     fn retrieve_ledger_id(&mut self, code: &str) -> Result<i32, WalletError> {
         let row = self
             .client
-            .query_one("SELECT id FROM ledgers WHERE code = $1", &[&code])?;
+            .query_opt("SELECT id FROM ledgers WHERE code = $1", &[&code])?
+            .ok_or_else(|| WalletError::LedgerNotFound(code.to_string()))?;
         Ok(row.get(0))
     }
 
-    fn proceed_spend(
+    // Ledger lookup for bulk paths, so each code is only queried once
+    fn cached_ledger_id(
         &mut self,
-        patron: &str,
-        outlay: &str,
-        amount: f64,
-        narration: &str,
-        created_at: Option<NaiveDateTime>,
-    ) -> Result<(), WalletError> {
-        if amount <= 0.0 {
-            return Err(WalletError::InvalidAmount(
-                "Amount must be positive".to_string(),
-            ));
+        cache: &mut HashMap<String, i32>,
+        code: &str,
+    ) -> Result<i32, WalletError> {
+        if let Some(id) = cache.get(code) {
+            return Ok(*id);
         }
+        let id = self.retrieve_ledger_id(code)?;
+        cache.insert(code.to_string(), id);
+        Ok(id)
+    }
 
-        let patron_id = self.retrieve_ledger_id(patron)?;
-        let outlay_id = self.retrieve_ledger_id(outlay)?;
-
-        if let Some(created_at) = created_at {
-            // Use the provided created_at date for both created_at and updated_at
-            self.client.execute(
-                "INSERT INTO proceedings (cr_from, db_to, amount, narration, created_at) 
-                 VALUES ($1, $2, $3, $4, $5)",
-                &[&patron_id, &outlay_id, &amount, &narration, &created_at],
-            )?;
-        } else {
-            // Let the database set created_at and updated_at to CURRENT_TIMESTAMP
-            self.client.execute(
-                "INSERT INTO proceedings (cr_from, db_to, amount, narration) VALUES ($1, $2, $3, $4)",
-                &[&patron_id, &outlay_id, &amount, &narration],
-            )?;
+    fn record_spend(&mut self, entry: SpendEntry) -> Result<(), WalletError> {
+        let resolved = self.resolve_spends(std::slice::from_ref(&entry))?;
+        let entry = &resolved[0].1;
+        if !self.confirm_not_duplicate(entry)? {
+            outln!("Spending not recorded.");
+            return Ok(());
         }
+        self.insert_resolved_spends(&resolved)?;
 
-        println!(
+        outln!(
             "Added spending: {} -> {}: {} ({})",
-            patron, outlay, amount, narration
+            entry.patron,
+            entry.outlay,
+            entry.amount,
+            entry.narration
         );
+        if let Some(tax) = entry.tax_amount {
+            match entry.tax_rate {
+                Some(rate) => outln!("Tax: {:.2} ({}%)", tax, rate),
+                None => outln!("Tax: {:.2}", tax),
+            }
+        }
+        if entry.pending {
+            match entry.clears_on {
+                Some(day) => outln!("Pending until it clears on {}", day),
+                None => outln!("Pending until confirmed with clear-item"),
+            }
+        }
         Ok(())
     }
 
-    fn generate_spending_report(&mut self, period: ReportPeriod) -> Result<(), WalletError> {
-        let now: DateTime<Utc> = Utc::now();
-        let (start_date_naive, end_date_naive, period_str): (
-            NaiveDateTime,
-            Option<NaiveDateTime>,
-            String,
-        ) = match &period {
-            ReportPeriod::Today => {
-                let start = now
-                    .with_hour(0)
-                    .and_then(|d| d.with_minute(0))
-                    .and_then(|d| d.with_second(0))
-                    .and_then(|d| d.with_nanosecond(0))
-                    .unwrap();
-                (start.naive_utc(), None, "Today".to_string())
-            }
-            ReportPeriod::Week => {
-                let start = now - Duration::days(now.weekday().num_days_from_monday() as i64)
-                    + Duration::hours(0)
-                    - Duration::minutes(now.minute() as i64)
-                    - Duration::seconds(now.second() as i64)
-                    - Duration::nanoseconds(now.nanosecond() as i64);
-                (start.naive_utc(), None, "This Week".to_string())
-            }
-            ReportPeriod::Month => {
-                let start = now
-                    .with_day(1)
-                    .and_then(|d| d.with_hour(0))
-                    .and_then(|d| d.with_minute(0))
-                    .and_then(|d| d.with_second(0))
-                    .and_then(|d| d.with_nanosecond(0))
-                    .unwrap();
-                (start.naive_utc(), None, "This Month".to_string())
-            }
-            ReportPeriod::All => {
-                let start =
-                    NaiveDateTime::parse_from_str("1970-01-01 00:00:00", "%Y-%m-%d %H:%M:%S")?;
-                (start, None, "All Time".to_string())
-            }
-            ReportPeriod::Date(date_str) => {
-                let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid date format: {}. Use YYYY-MM-DD",
-                        date_str
-                    ))
-                })?;
-                let start = date.and_hms_opt(0, 0, 0).unwrap();
-                let end = date.and_hms_opt(23, 59, 59).unwrap();
-                (start, Some(end), format!("Date: {}", date_str))
-            }
-            ReportPeriod::FromTo { from, to } => {
-                let from_date = NaiveDate::parse_from_str(from, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid 'from' date format: {}. Use YYYY-MM-DD",
-                        from
-                    ))
-                })?;
-                let to_date = NaiveDate::parse_from_str(to, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid 'to' date format: {}. Use YYYY-MM-DD",
-                        to
-                    ))
-                })?;
-                if from_date > to_date {
-                    return Err(WalletError::DateRangeError(
-                        "The 'from' date must be earlier than or equal to the 'to' date."
-                            .to_string(),
-                    ));
-                }
-                let start = from_date.and_hms_opt(0, 0, 0).unwrap();
-                let end = to_date.and_hms_opt(23, 59, 59).unwrap();
-                (start, Some(end), format!("From {} to {}", from, to))
-            }
-        };
+    // Records several spends with one prepared statement inside a single
+    // transaction, so either all entries are stored or none are.
+    fn proceed_spend_batch(&mut self, entries: &[SpendEntry]) -> Result<usize, WalletError> {
+        let resolved = self.resolve_spends(entries)?;
+        self.insert_resolved_spends(&resolved)
+    }
 
-        let query = match &period {
-            ReportPeriod::All => {
-                "
-                SELECT 
-                    l.code, 
-                    l.name, 
-                    CASE 
-                        WHEN l.kind = 'LIABILITY' THEN 
-                            COALESCE((
-                                SELECT SUM(p1.amount) 
-                                FROM proceedings p1 
-                                WHERE p1.db_to = l.id
-                            ), 0) - COALESCE((
-                                SELECT SUM(p2.amount) 
-                                FROM proceedings p2 
-                                WHERE p2.cr_from = l.id
-                            ), 0)
-                        ELSE 
-                            COALESCE((
-                                SELECT SUM(p3.amount) 
-                                FROM proceedings p3 
-                                WHERE p3.db_to = l.id
-                            ), 0)
-                    END as amount
-                FROM ledgers l
-                ORDER BY amount DESC
-            "
-            }
-            ReportPeriod::Date(_) => {
-                "
-                SELECT 
-                    l.code, 
-                    l.name, 
-                    CASE 
-                        WHEN l.kind = 'LIABILITY' THEN 
-                            COALESCE((
-                                SELECT SUM(p1.amount) 
-                                FROM proceedings p1 
-                                WHERE p1.db_to = l.id 
-                                AND p1.created_at >= $1 AND p1.created_at <= $2
-                            ), 0) - COALESCE((
-                                SELECT SUM(p2.amount) 
-                                FROM proceedings p2 
-                                WHERE p2.cr_from = l.id 
-                                AND p2.created_at >= $1 AND p2.created_at <= $2
-                            ), 0)
-                        ELSE 
-                            COALESCE((
-                                SELECT SUM(p3.amount) 
-                                FROM proceedings p3 
-                                WHERE p3.db_to = l.id 
-                                AND p3.created_at >= $1 AND p3.created_at <= $2
-                            ), 0)
-                    END as amount
-                FROM ledgers l
-                ORDER BY amount DESC
-            "
-            }
-            _ => {
-                "
-                SELECT 
-                    l.code, 
-                    l.name, 
-                    CASE 
-                        WHEN l.kind = 'LIABILITY' THEN 
-                            COALESCE((
-                                SELECT SUM(p1.amount) 
-                                FROM proceedings p1 
-                                WHERE p1.db_to = l.id 
-                                AND p1.created_at >= $1
-                            ), 0) - COALESCE((
-                                SELECT SUM(p2.amount) 
-                                FROM proceedings p2 
-                                WHERE p2.cr_from = l.id 
-                                AND p2.created_at >= $1
-                            ), 0)
-                        ELSE 
-                            COALESCE((
-                                SELECT SUM(p3.amount) 
-                                FROM proceedings p3 
-                                WHERE p3.db_to = l.id 
-                                AND p3.created_at >= $1
-                            ), 0)
-                    END as amount
-                FROM ledgers l
-                ORDER BY amount DESC
-            "
-            }
-        };
-        let rows = match &period {
-            ReportPeriod::All => self.client.query(query, &[])?,
-            ReportPeriod::Date(_) => self
-                .client
-                .query(query, &[&start_date_naive, &end_date_naive.unwrap()])?,
-            _ => self.client.query(query, &[&start_date_naive])?,
-        };
+    // Validates each entry, looks up its [patron, outlay] ledger ids and
+    // applies the ledgers' policies, ready for insert_resolved_spends
+    fn resolve_spends(
+        &mut self,
+        entries: &[SpendEntry],
+    ) -> Result<Vec<([i32; 2], SpendEntry)>, WalletError> {
+        let mut resolved = Vec::with_capacity(entries.len());
+        let mut ledger_ids: HashMap<String, i32> = HashMap::new();
+        for entry in entries {
+            entry.validate()?;
+            let patron_id = self.cached_ledger_id(&mut ledger_ids, &entry.patron)?;
+            let outlay_id = self.cached_ledger_id(&mut ledger_ids, &entry.outlay)?;
+            let mut entry = entry.clone();
+            self.apply_policies([patron_id, outlay_id], &mut entry)?;
+            resolved.push(([patron_id, outlay_id], entry));
+        }
+        Ok(resolved)
+    }
 
-        println!("\nSpending Report ({}):", period_str);
-        println!("{:<10} {:<30} {:<15}", "Code", "Name", "Net Amount");
-        println!("{:-<55}", "");
-        let mut grand_total: f64 = 0.0;
-        for row in rows.iter() {
-            let code: String = row.get(0);
-            let name: String = row.get(1);
-            let net_amount: f64 = row.get(2);
-            grand_total += net_amount;
-            println!("{:<10} {:<30} {:<15.2}", code, name, net_amount);
-        }
-        println!("{:-<55}", "");
-        println!("{:<40} {:<15.2}", "Grand Total", grand_total);
+    fn insert_resolved_spends(
+        &mut self,
+        resolved: &[([i32; 2], SpendEntry)],
+    ) -> Result<usize, WalletError> {
+        self.ensure_months_unlocked(resolved.iter().map(|(_, entry)| entry))?;
+
+        let mut transaction = self.client.transaction()?;
+        // Without an explicit date, fall back to the same LOCALTIMESTAMP the
+        // column default would use
+        let statement = transaction.prepare(
+            "INSERT INTO proceedings
+                 (cr_from, db_to, amount, narration, created_at, pending, clears_on, payee, project, tags,
+                  tax_amount, tax_rate, cleared_at)
+             VALUES ($1, $2, $3::float8, $4, COALESCE($5::timestamp, LOCALTIMESTAMP), $6, $7, $8, $9, $10,
+                     $11::float8, $12::float8, $13::timestamp)",
+        )?;
+        for ([patron_id, outlay_id], entry) in resolved.iter() {
+            transaction.execute(
+                &statement,
+                &[
+                    patron_id,
+                    outlay_id,
+                    &entry.amount,
+                    &entry.narration,
+                    &entry.created_at,
+                    &entry.pending,
+                    &entry.clears_on,
+                    &entry.payee,
+                    &entry.project,
+                    &entry.tags,
+                    &entry.tax_amount,
+                    &entry.tax_rate,
+                    &entry.cleared_at,
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(resolved.len())
+    }
+
+    // Checks each distinct month touched by `entries` against period_locks
+    fn ensure_months_unlocked<'a>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'a SpendEntry>,
+    ) -> Result<(), WalletError> {
+        let today = dates::today();
+        let mut months = BTreeMap::new();
+        for entry in entries {
+            let day = entry.created_at.map(|d| d.date()).unwrap_or(today);
+            months.entry(day.with_day(1).unwrap()).or_insert(day);
+        }
+        for day in months.into_values() {
+            self.ensure_unlocked(day)?;
+        }
+        Ok(())
+    }
+
+    fn generate_spending_report(
+        &mut self,
+        period: ReportPeriod,
+        fx_args: &FxArgs,
+    ) -> Result<(), WalletError> {
+        let (start, end, _) = period.bounds()?;
+        let fx = self.fx_column(fx_args, start.date(), end.map(|end| end.date()))?;
+        let grand_total = store::spending_report(self, &period, &fx)?;
+        if let Some(spec) = self.report_footer.clone() {
+            let vars = self.footer_vars(&period, grand_total)?;
+            footer::print(&spec, 40, vars);
+        }
+        fx.footnote();
         Ok(())
     }
+
+    // Net amount per ledger (code, name, amount) between `start` and `end`,
+    // largest first
+    fn spending_totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+    ) -> Result<Vec<(String, String, f64)>, WalletError> {
+        // One pass over proceedings: each proceeding debits db_to and, while
+        // cr_from is a LIABILITY, reduces cr_from's balance
+        let query = format!(
+            "
+            WITH {}
+            SELECT
+                l.code,
+                l.name,
+                COALESCE(t.amount, 0)::float8 as amount
+            FROM ledgers l
+            LEFT JOIN (
+                SELECT x.ledger_id, SUM(x.amount) as amount
+                FROM proceedings p
+                JOIN ledgers c ON c.id = p.cr_from
+                LEFT JOIN kind_spans k ON k.ledger_id = p.cr_from
+                    AND k.effective_from <= p.created_at
+                    AND (k.effective_to IS NULL OR p.created_at < k.effective_to)
+                CROSS JOIN LATERAL (VALUES
+                    (p.db_to, p.amount),
+                    (p.cr_from, CASE
+                        WHEN COALESCE(k.kind, c.kind) = 'LIABILITY' THEN -p.amount
+                        ELSE 0
+                    END)
+                ) AS x(ledger_id, amount)
+                WHERE p.created_at >= $1::timestamp
+                    AND ($2::timestamp IS NULL OR p.created_at <= $2::timestamp)
+                    AND NOT p.pending
+                GROUP BY x.ledger_id
+            ) t ON t.ledger_id = l.id
+            ORDER BY amount DESC
+            ",
+            KIND_SPANS
+        );
+        let rows = self.client.query(&query, &[&start, &end])?;
+        Ok(rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect())
+    }
     fn generate_ledger_report(
         &mut self,
         ledger_code: &str,
         period: ReportPeriod,
+        paging: &PageArgs,
+        fx_args: &FxArgs,
     ) -> Result<(), WalletError> {
         let ledger_id = self.retrieve_ledger_id(ledger_code)?;
         let ledger_name: String = self
@@ -331,226 +606,170 @@ impl WalletDB {
             .query_one("SELECT name FROM ledgers WHERE id = $1", &[&ledger_id])?
             .get(0);
 
-        let now: DateTime<Utc> = Utc::now();
-        let (start_date_naive, end_date_naive, period_str): (
-            NaiveDateTime,
-            Option<NaiveDateTime>,
-            String,
-        ) = match &period {
-            ReportPeriod::Today => {
-                let start = now
-                    .with_hour(0)
-                    .and_then(|d| d.with_minute(0))
-                    .and_then(|d| d.with_second(0))
-                    .and_then(|d| d.with_nanosecond(0))
-                    .unwrap();
-                (start.naive_utc(), None, "Today".to_string())
-            }
-            ReportPeriod::Week => {
-                let start = now - Duration::days(now.weekday().num_days_from_monday() as i64)
-                    + Duration::hours(0)
-                    - Duration::minutes(now.minute() as i64)
-                    - Duration::seconds(now.second() as i64)
-                    - Duration::nanoseconds(now.nanosecond() as i64);
-                (start.naive_utc(), None, "This Week".to_string())
-            }
-            ReportPeriod::Month => {
-                let start = now
-                    .with_day(1)
-                    .and_then(|d| d.with_hour(0))
-                    .and_then(|d| d.with_minute(0))
-                    .and_then(|d| d.with_second(0))
-                    .and_then(|d| d.with_nanosecond(0))
-                    .unwrap();
-                (start.naive_utc(), None, "This Month".to_string())
-            }
-            ReportPeriod::All => {
-                let start =
-                    NaiveDateTime::parse_from_str("1970-01-01 00:00:00", "%Y-%m-%d %H:%M:%S")?;
-                (start, None, "All Time".to_string())
-            }
-            ReportPeriod::Date(date_str) => {
-                let date = NaiveDate::parse_from_str(date_str, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid date format: {}. Use YYYY-MM-DD",
-                        date_str
-                    ))
-                })?;
-                let start = date.and_hms_opt(0, 0, 0).unwrap();
-                let end = date.and_hms_opt(23, 59, 59).unwrap();
-                (start, Some(end), format!("Date: {}", date_str))
-            }
-            ReportPeriod::FromTo { from, to } => {
-                let from_date = NaiveDate::parse_from_str(from, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid 'from' date format: {}. Use YYYY-MM-DD",
-                        from
-                    ))
-                })?;
-                let to_date = NaiveDate::parse_from_str(to, "%Y-%m-%d").map_err(|_| {
-                    WalletError::InvalidDate(format!(
-                        "Invalid 'to' date format: {}. Use YYYY-MM-DD",
-                        to
-                    ))
-                })?;
-                if from_date > to_date {
-                    return Err(WalletError::DateRangeError(
-                        "The 'from' date must be earlier than or equal to the 'to' date."
-                            .to_string(),
-                    ));
-                }
-                let start = from_date.and_hms_opt(0, 0, 0).unwrap();
-                let end = to_date.and_hms_opt(23, 59, 59).unwrap();
-                (start, Some(end), format!("From {} to {}", from, to))
-            }
-        };
+        let (start_date_naive, end_date_naive, period_str) = period.bounds()?;
+        let fx = self.fx_column(
+            fx_args,
+            start_date_naive.date(),
+            end_date_naive.map(|end| end.date()),
+        )?;
 
         let query = match &period {
             ReportPeriod::All => {
                 "
-                SELECT p.created_at, 
+                SELECT p.created_at::timestamp, 
                        CASE 
                            WHEN p.cr_from = $1 THEN (SELECT code FROM ledgers WHERE id = p.db_to)
                            ELSE (SELECT code FROM ledgers WHERE id = p.cr_from)
                        END as counterparty,
                        p.narration,
-                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END as credit_amount,
-                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END as debit_amount
+                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END::float8 as credit_amount,
+                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END::float8 as debit_amount,
+                       SUM(CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_credits,
+                       SUM(CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_debits,
+                       COUNT(*) OVER () as total_rows
                 FROM proceedings p
-                WHERE p.cr_from = $1 OR p.db_to = $1
+                WHERE (p.cr_from = $1 OR p.db_to = $1) AND NOT p.pending
                 ORDER BY p.created_at DESC
             "
             }
             ReportPeriod::Date(_) | ReportPeriod::FromTo { .. } => {
                 "
-                SELECT p.created_at, 
+                SELECT p.created_at::timestamp, 
                        CASE 
                            WHEN p.cr_from = $1 THEN (SELECT code FROM ledgers WHERE id = p.db_to)
                            ELSE (SELECT code FROM ledgers WHERE id = p.cr_from)
                        END as counterparty,
                        p.narration,
-                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END as credit_amount,
-                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END as debit_amount
+                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END::float8 as credit_amount,
+                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END::float8 as debit_amount,
+                       SUM(CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_credits,
+                       SUM(CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_debits,
+                       COUNT(*) OVER () as total_rows
                 FROM proceedings p
-                WHERE (p.cr_from = $1 OR p.db_to = $1) AND p.created_at >= $2 AND p.created_at <= $3
+                WHERE (p.cr_from = $1 OR p.db_to = $1) AND p.created_at >= $2::timestamp AND p.created_at <= $3::timestamp
+                    AND NOT p.pending
                 ORDER BY p.created_at DESC
             "
             }
             _ => {
                 "
-                SELECT p.created_at, 
+                SELECT p.created_at::timestamp, 
                        CASE 
                            WHEN p.cr_from = $1 THEN (SELECT code FROM ledgers WHERE id = p.db_to)
                            ELSE (SELECT code FROM ledgers WHERE id = p.cr_from)
                        END as counterparty,
                        p.narration,
-                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END as credit_amount,
-                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END as debit_amount
+                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END::float8 as credit_amount,
+                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END::float8 as debit_amount,
+                       SUM(CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_credits,
+                       SUM(CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END) OVER ()::float8 as total_debits,
+                       COUNT(*) OVER () as total_rows
                 FROM proceedings p
-                WHERE (p.cr_from = $1 OR p.db_to = $1) AND p.created_at >= $2
+                WHERE (p.cr_from = $1 OR p.db_to = $1) AND p.created_at >= $2::timestamp AND NOT p.pending
                 ORDER BY p.created_at DESC
             "
             }
         };
 
-        let rows = match &period {
-            ReportPeriod::All => self.client.query(query, &[&ledger_id])?,
-            ReportPeriod::Date(_) | ReportPeriod::FromTo { .. } => self.client.query(
-                query,
-                &[&ledger_id, &start_date_naive, &end_date_naive.unwrap()],
-            )?,
-            _ => self.client.query(query, &[&ledger_id, &start_date_naive])?,
+        let query = format!("{} {}", query, paging.sql(None));
+        let query = query.as_str();
+
+        let end_date_naive = end_date_naive.unwrap_or_default();
+        let params: Vec<&(dyn ToSql + Sync)> = match &period {
+            ReportPeriod::All => vec![&ledger_id],
+            ReportPeriod::Date(_) | ReportPeriod::FromTo { .. } => {
+                vec![&ledger_id, &start_date_naive, &end_date_naive]
+            }
+            _ => vec![&ledger_id, &start_date_naive],
         };
+        // Rows are streamed rather than collected so long ledgers print in
+        // constant memory
+        let mut rows = self.client.query_raw(query, params)?;
+        let first = rows.next()?;
 
-        println!(
+        outln!(
             "\nLedger Report for {} - {} ({}):",
-            ledger_code, ledger_name, period_str
+            ledger_code,
+            ledger_name,
+            period_str
         );
-        println!(
-            "{:<20} {:<10} {:<30} {:<15} {:<15}",
-            "Date", "Counterparty", "Narration", "Credit", "Debit"
+        outln!(
+            "{:<20} {:<10} {:<30} {:<15} {:<15}{}",
+            "Date",
+            "Counterparty",
+            "Narration",
+            "Credit",
+            "Debit",
+            fx.header()
         );
-        println!("{:-<90}", "");
+        outln!("{}", fx.rule(90));
 
-        let mut total_credits: f64 = 0.0;
-        let mut total_debits: f64 = 0.0;
+        // Totals cover the whole period, not just the page being shown
+        let (total_credits, total_debits, total_rows): (f64, f64, i64) = first
+            .as_ref()
+            .map(|row| (row.get(5), row.get(6), row.get(7)))
+            .unwrap_or((0.0, 0.0, 0));
 
-        for row in rows.iter() {
+        let mut pager = paging.pager();
+        let mut shown = 0;
+        let mut next = first;
+        while let Some(row) = next {
             let created_at: NaiveDateTime = row.get(0);
             let counterparty: String = row.get(1);
             let narration: String = row.get(2);
             let credit_amount: f64 = row.get(3);
             let debit_amount: f64 = row.get(4);
 
-            total_credits += credit_amount;
-            total_debits += debit_amount;
-
-            println!(
-                "{:<20} {:<10} {:<30} {:<15.2} {:<15.2}",
+            // The converted column carries the row's net effect, debits
+            // positive as in the net balance
+            let line = format!(
+                "{:<20} {:<10} {:<30} {:<15.2} {:<15.2}{}",
                 created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
                 counterparty,
                 narration,
                 credit_amount,
-                debit_amount
+                debit_amount,
+                fx.cell(debit_amount - credit_amount)
             );
+            if !pager.line(&line) {
+                break;
+            }
+            shown += 1;
+            next = rows.next()?;
+        }
+        drop(rows);
+        if shown == 0 && paging.offset > 0 {
+            // Window totals are only available on returned rows
+            outln!("{}", fx.rule(90));
+            outln!("Showing {}", paging.describe(0, total_rows));
+            return Ok(());
         }
 
         let net_balance = total_debits - total_credits;
 
-        println!("{:-<90}", "");
-        println!(
-            "{:<60} {:<15.2} {:<15.2}",
-            "Totals", total_credits, total_debits
+        outln!("{}", fx.rule(90));
+        outln!(
+            "{:<60} {:<15.2} {:<15.2}{}",
+            "Totals",
+            total_credits,
+            total_debits,
+            fx.blank()
         );
-        println!(
-            "{:<60} {:<15.2}",
-            "Net Balance (Debits - Credits)", net_balance
-        );
-
-        Ok(())
-    }
-    fn generate_recent_transactions_report(&mut self) -> Result<(), WalletError> {
-        let query = "
-            SELECT p.created_at, 
-                   (SELECT code FROM ledgers WHERE id = p.cr_from) as cr_from_code,
-                   (SELECT code FROM ledgers WHERE id = p.db_to) as db_to_code,
-                   p.amount,
-                   p.narration
-            FROM proceedings p
-            ORDER BY p.created_at DESC
-            LIMIT 10
-        ";
-
-        let rows = self.client.query(query, &[])?;
-
-        println!("\nRecent Transactions Report (Last 10):");
-        println!(
-            "{:<20} {:<10} {:<10} {:<15} {:<30}",
-            "Date", "From", "To", "Amount", "Narration"
+        outln!(
+            "{:<60} {:<15.2}{}{}",
+            "Net Balance (Debits - Credits)",
+            net_balance,
+            fx.blank(),
+            fx.cell(net_balance)
         );
-        println!("{:-<85}", "");
-
-        for row in rows.iter() {
-            let created_at: NaiveDateTime = row.get(0);
-            let cr_from_code: String = row.get(1);
-            let db_to_code: String = row.get(2);
-            let amount: f64 = row.get(3);
-            let narration: String = row.get(4);
-
-            println!(
-                "{:<20} {:<10} {:<10} {:<15.2} {:<30}",
-                created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
-                cr_from_code,
-                db_to_code,
-                amount,
-                narration
-            );
+        if paging.limit.is_some() || paging.offset > 0 {
+            outln!("Showing {}", paging.describe(shown, total_rows));
         }
+        fx.footnote();
+        self.print_uncleared(ledger_id, net_balance)?;
 
-        println!("{:-<85}", "");
         Ok(())
     }
-
     // fn generate_calendar_report(&mut self) -> Result<(), WalletError> {
     //     let now: DateTime<Utc> = Utc::now();
     //     // Start of the month
@@ -625,8 +844,9 @@ impl WalletDB {
         &mut self,
         month_arg: Option<&str>,
         cap: Option<f64>,
+        fx_args: &FxArgs,
     ) -> Result<(), WalletError> {
-        let now: DateTime<Utc> = Utc::now();
+        let now = dates::today();
         let current_year = now.year();
         let current_month = now.month();
 
@@ -676,12 +896,7 @@ impl WalletDB {
         // End of the month: if it's the current month, end at the current day; otherwise, use the last day of the month
         let end_date = if target_month == current_month && target_year == current_year {
             // End at the end of today
-            now.with_hour(23)
-                .and_then(|d| d.with_minute(59))
-                .and_then(|d| d.with_second(59))
-                .and_then(|d| d.with_nanosecond(999_999_999))
-                .unwrap()
-                .naive_utc()
+            now.and_hms_nano_opt(23, 59, 59, 999_999_999).unwrap()
         } else {
             // Find the last day of the target month
             let next_month = if target_month == 12 {
@@ -699,66 +914,33 @@ impl WalletDB {
                 .unwrap()
         };
 
-        let query = "
-        SELECT
-            DATE(p.created_at) as day,
-            SUM(CASE
-                    WHEN l.kind = 'LIABILITY' THEN
-                        (CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END) -
-                        (CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END)
-                    ELSE
-                        CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END
-                END) as daily_amount
-        FROM proceedings p
-        JOIN ledgers l ON p.db_to = l.id OR p.cr_from = l.id
-        WHERE p.created_at >= $1 AND p.created_at <= $2
-        GROUP BY DATE(p.created_at)
-        HAVING SUM(CASE
-                       WHEN l.kind = 'LIABILITY' THEN
-                           (CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END) -
-                           (CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END)
-                       ELSE
-                           CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END
-                   END) != 0
-        ORDER BY DATE(p.created_at)
-    ";
-
-        // Query to get daily totals, focusing on debits to non-liability ledgers
-        // let query = "
-        //     SELECT
-        //         DATE(p.created_at) as day,
-        //         SUM(p.amount) as daily_amount
-        //     FROM proceedings p
-        //     JOIN ledgers l ON p.db_to = l.id
-        //     WHERE p.created_at >= $1 AND p.created_at <= $2
-        //         AND l.kind != 'LIABILITY'
-        //     GROUP BY DATE(p.created_at)
-        //     HAVING SUM(p.amount) > 0
-        //     ORDER BY DATE(p.created_at)
-        // ";
-
-        let rows = self.client.query(query, &[&start_date, &end_date])?;
+        let fx = self.fx_column(fx_args, start_date.date(), Some(end_date.date()))?;
+        let days = self.daily_totals(start_date, end_date)?;
 
         // Format the report header with the month and year
         let mut report_header = format!("{} {}", month_name, target_year);
         if let Some(cap_value) = cap {
             report_header = format!("{} (Daily Cap: {:.2})", report_header, cap_value);
         }
-        println!("\nDaily Spending Report for {}:", report_header);
+        outln!("\nDaily Spending Report for {}:", report_header);
         // Update the header to include a "Difference" column if a cap is specified
         if cap.is_some() {
-            println!("{:<15} {:<15} {:<15}", "Date", "Total Spent", "Skimp");
-            println!("{:-<45}", "");
+            outln!(
+                "{:<15} {:<15} {:<15}{}",
+                "Date",
+                "Total Spent",
+                "Skimp",
+                fx.header()
+            );
+            outln!("{}", fx.rule(45));
         } else {
-            println!("{:<15} {:<15}", "Date", "Total Spent");
-            println!("{:-<30}", "");
+            outln!("{:<15} {:<15}{}", "Date", "Total Spent", fx.header());
+            outln!("{}", fx.rule(30));
         }
 
         let mut grand_total: f64 = 0.0;
         let mut skimp: f64 = 0.0;
-        for row in rows.iter() {
-            let day: NaiveDate = row.get(0);
-            let daily_amount: f64 = row.get(1);
+        for &(day, daily_amount) in days.iter() {
             grand_total += daily_amount;
 
             if let Some(cap_value) = cap {
@@ -771,55 +953,104 @@ impl WalletDB {
                     // Overspent: show in red
                     format!("{:.2}", difference).red()
                 };
-                println!(
-                    "{:<15} {:<15.2} {:<15}",
+                outln!(
+                    "{:<15} {:<15.2} {:<15}{}",
                     day.format("%Y-%m-%d").to_string(),
                     daily_amount,
-                    difference_str
+                    difference_str,
+                    fx.cell(daily_amount)
                 );
             } else {
-                println!(
-                    "{:<15} {:<15.2}",
+                outln!(
+                    "{:<15} {:<15.2}{}",
                     day.format("%Y-%m-%d").to_string(),
-                    daily_amount
+                    daily_amount,
+                    fx.cell(daily_amount)
                 );
             }
         }
 
         if cap.is_some() {
-            println!("{:-<45}", "");
+            outln!("{}", fx.rule(45));
+            outln!(
+                "{:<15} {:<15.2} {:<15}{}",
+                "Grand Total",
+                grand_total,
+                skimp,
+                fx.cell(grand_total)
+            );
         } else {
-            println!("{:-<30}", "");
+            outln!("{}", fx.rule(30));
+            outln!(
+                "{:<15} {:<15.2}{}",
+                "Grand Total",
+                grand_total,
+                fx.cell(grand_total)
+            );
         }
-        println!("{:<15} {:<15.2} {:<15}", "Grand Total", grand_total, skimp);
+        fx.footnote();
 
         Ok(())
     }
 
-    // New method to list all ledgers (helpful for debugging or user reference)
-    fn list_ledgers(&mut self) -> Result<(), WalletError> {
-        let rows = self.client.query(
-            "SELECT code, name, sort, kind FROM ledgers ORDER BY code",
-            &[],
-        )?;
-
-        println!("\nList of Ledgers:");
-        println!(
-            "{:<10} {:<30} {:<10} {:<10}",
-            "Code", "Name", "Sort", "Kind"
-        );
-        println!("{:-<60}", "");
-        for row in rows {
-            let code: String = row.get(0);
-            let name: String = row.get(1);
-            let sort: String = row.get(2);
-            let kind: String = row.get(3);
-            println!("{:<10} {:<30} {:<10} {:<10}", code, name, sort, kind);
-        }
-        Ok(())
-    }
+    // Per-day totals between `start` and `end` (inclusive) as shown by the
+    // calendar: debits, with LIABILITY ledgers netted against their credits
+    fn daily_totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> Result<Vec<(NaiveDate, f64)>, WalletError> {
+        let query = "
+        SELECT
+            DATE(p.created_at) as day,
+            SUM(CASE
+                    WHEN ledger_kind_at(l.id, p.created_at) = 'LIABILITY' THEN
+                        (CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END) -
+                        (CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END)
+                    ELSE
+                        CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END
+                END)::float8 as daily_amount
+        FROM proceedings p
+        JOIN ledgers l ON p.db_to = l.id OR p.cr_from = l.id
+        WHERE p.created_at >= $1::timestamp AND p.created_at <= $2::timestamp AND NOT p.pending
+        GROUP BY DATE(p.created_at)
+        HAVING SUM(CASE
+                       WHEN ledger_kind_at(l.id, p.created_at) = 'LIABILITY' THEN
+                           (CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END) -
+                           (CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END)
+                       ELSE
+                           CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END
+                   END) != 0
+        ORDER BY DATE(p.created_at)
+    ";
 
-    fn setup_db(&mut self) -> Result<(), WalletError> {
+        // Query to get daily totals, focusing on debits to non-liability ledgers
+        // let query = "
+        //     SELECT
+        //         DATE(p.created_at) as day,
+        //         SUM(p.amount) as daily_amount
+        //     FROM proceedings p
+        //     JOIN ledgers l ON p.db_to = l.id
+        //     WHERE p.created_at >= $1 AND p.created_at <= $2
+        //         AND l.kind != 'LIABILITY'
+        //     GROUP BY DATE(p.created_at)
+        //     HAVING SUM(p.amount) > 0
+        //     ORDER BY DATE(p.created_at)
+        // ";
+
+        let rows = self.client.query(query, &[&start, &end])?;
+        Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+    }
+
+    // New method to list all ledgers (helpful for debugging or user reference)
+    fn ledger_codes(&mut self) -> Result<Vec<String>, WalletError> {
+        let rows = self
+            .client
+            .query("SELECT code FROM ledgers ORDER BY code", &[])?;
+        Ok(rows.iter().map(|row| row.get(0)).collect())
+    }
+
+    fn setup_db(&mut self) -> Result<(), WalletError> {
         self.client.batch_execute(
             "
             CREATE TABLE IF NOT EXISTS ledgers (
@@ -837,29 +1068,394 @@ impl WalletDB {
                 id SERIAL PRIMARY KEY,
                 cr_from INTEGER NOT NULL REFERENCES ledgers(id),
                 db_to INTEGER NOT NULL REFERENCES ledgers(id),
-                amount DOUBLE PRECISION NOT NULL,
+                amount NUMERIC(14, 2) NOT NULL,
+                currency CHAR(3) NOT NULL DEFAULT 'INR',
                 narration TEXT NOT NULL,
+                uuid UUID NOT NULL UNIQUE DEFAULT gen_random_uuid(),
+                effective_date DATE NOT NULL,
+                created_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMPTZ DEFAULT CURRENT_TIMESTAMP,
+                pending BOOLEAN NOT NULL DEFAULT false,
+                clears_on DATE,
+                payee TEXT,
+                project TEXT,
+                tags TEXT[] NOT NULL DEFAULT '{}',
+                tax_amount NUMERIC(14, 2),
+                tax_rate NUMERIC(5, 2),
+                cleared_at TIMESTAMPTZ
+            );
+
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS pending BOOLEAN NOT NULL DEFAULT false;
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS clears_on DATE;
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS payee TEXT;
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS project TEXT;
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS tags TEXT[] NOT NULL DEFAULT '{}';
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS tax_amount NUMERIC(14, 2);
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS tax_rate NUMERIC(5, 2);
+            ALTER TABLE proceedings ADD COLUMN IF NOT EXISTS cleared_at TIMESTAMPTZ;
+
+            -- Older databases stored wall-clock times; they are read in the
+            -- session's time zone
+            DROP FUNCTION IF EXISTS ledger_kind_at(INTEGER, TIMESTAMP);
+            DO $$
+            DECLARE
+                col TEXT;
+            BEGIN
+                FOR col IN
+                    SELECT column_name FROM information_schema.columns
+                    WHERE table_schema = current_schema()
+                        AND table_name = 'proceedings'
+                        AND column_name IN ('created_at', 'updated_at', 'cleared_at')
+                        AND data_type = 'timestamp without time zone'
+                LOOP
+                    EXECUTE format('ALTER TABLE proceedings ALTER COLUMN %I TYPE TIMESTAMPTZ', col);
+                END LOOP;
+            END $$;
+
+            CREATE INDEX IF NOT EXISTS idx_proceedings_created_at ON proceedings (created_at);
+            CREATE INDEX IF NOT EXISTS idx_proceedings_cr_from ON proceedings (cr_from);
+            CREATE INDEX IF NOT EXISTS idx_proceedings_db_to ON proceedings (db_to);
+            CREATE INDEX IF NOT EXISTS idx_proceedings_pending ON proceedings (clears_on) WHERE pending;
+
+            CREATE TABLE IF NOT EXISTS envelopes (
+                id SERIAL PRIMARY KEY,
+                ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+                month DATE NOT NULL,
+                assigned DOUBLE PRECISION NOT NULL DEFAULT 0,
+                carried_in DOUBLE PRECISION NOT NULL DEFAULT 0,
                 created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (ledger_id, month)
+            );
+
+            CREATE TABLE IF NOT EXISTS period_locks (
+                month DATE PRIMARY KEY,
+                locked_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS ledger_kinds (
+                id SERIAL PRIMARY KEY,
+                ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+                kind VARCHAR(20) NOT NULL,
+                effective_from DATE NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (ledger_id, effective_from)
+            );
+
+            CREATE TABLE IF NOT EXISTS goals (
+                id SERIAL PRIMARY KEY,
+                name VARCHAR(30) NOT NULL UNIQUE,
+                target NUMERIC(14, 2) NOT NULL,
+                target_date DATE,
+                ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS ledger_policies (
+                ledger_id INTEGER PRIMARY KEY REFERENCES ledgers(id),
+                require_payee BOOLEAN NOT NULL DEFAULT false,
+                require_project BOOLEAN NOT NULL DEFAULT false,
+                required_tag VARCHAR(30),
+                default_narration TEXT,
                 updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
             );
+
+            CREATE TABLE IF NOT EXISTS invoices (
+                id SERIAL PRIMARY KEY,
+                ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+                amount NUMERIC(14, 2) NOT NULL,
+                issued_on DATE NOT NULL DEFAULT CURRENT_DATE,
+                due_on DATE NOT NULL,
+                note TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS invoice_payments (
+                invoice_id INTEGER NOT NULL REFERENCES invoices(id) ON DELETE CASCADE,
+                proceeding_id INTEGER NOT NULL REFERENCES proceedings(id),
+                amount NUMERIC(14, 2) NOT NULL,
+                PRIMARY KEY (invoice_id, proceeding_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_invoices_ledger_id ON invoices (ledger_id);
+
+            CREATE TABLE IF NOT EXISTS links (
+                id SERIAL PRIMARY KEY,
+                from_id INTEGER NOT NULL REFERENCES proceedings(id) ON DELETE CASCADE,
+                to_id INTEGER NOT NULL REFERENCES proceedings(id) ON DELETE CASCADE,
+                kind VARCHAR(20) NOT NULL,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (from_id, to_id),
+                CHECK (from_id <> to_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_links_to_id ON links (to_id);
+
+            CREATE TABLE IF NOT EXISTS fx_rates (
+                currency CHAR(3) NOT NULL,
+                rate_on DATE NOT NULL,
+                rate NUMERIC(14, 6) NOT NULL CHECK (rate > 0),
+                PRIMARY KEY (currency, rate_on)
+            );
+
+            CREATE TABLE IF NOT EXISTS snapshots (
+                id SERIAL PRIMARY KEY,
+                ledger_id INTEGER NOT NULL REFERENCES ledgers(id),
+                balance NUMERIC(14, 2) NOT NULL,
+                taken_at TIMESTAMP NOT NULL DEFAULT LOCALTIMESTAMP
+            );
+
+            CREATE TABLE IF NOT EXISTS snapshot_items (
+                snapshot_id INTEGER NOT NULL REFERENCES snapshots(id) ON DELETE CASCADE,
+                proceeding_id INTEGER NOT NULL,
+                amount NUMERIC(14, 2) NOT NULL,
+                PRIMARY KEY (snapshot_id, proceeding_id)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_snapshots_ledger_id ON snapshots (ledger_id, taken_at);
+
+            CREATE TABLE IF NOT EXISTS templates (
+                id SERIAL PRIMARY KEY,
+                name VARCHAR(30) NOT NULL UNIQUE,
+                patron_id INTEGER NOT NULL REFERENCES ledgers(id),
+                outlay_id INTEGER NOT NULL REFERENCES ledgers(id),
+                amount NUMERIC(14, 2) NOT NULL,
+                narration TEXT,
+                created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE OR REPLACE FUNCTION ledger_kind_at(p_ledger_id INTEGER, p_at TIMESTAMPTZ)
+            RETURNS VARCHAR AS $$
+                SELECT COALESCE(
+                    (SELECT k.kind FROM ledger_kinds k
+                     WHERE k.ledger_id = p_ledger_id AND k.effective_from <= p_at
+                     ORDER BY k.effective_from DESC
+                     LIMIT 1),
+                    (SELECT l.kind FROM ledgers l WHERE l.id = p_ledger_id)
+                )
+            $$ LANGUAGE SQL STABLE;
             ",
         )?;
-        print!("Db setup completed successfully");
+        if self.schema_version()? == 2 {
+            self.client.batch_execute(upgrade::V2_OBJECTS)?;
+        } else {
+            outln!("Proceedings still use the v1 schema; run `upgrade` to convert them.");
+        }
+        out!("Db setup completed successfully");
         Ok(())
     }
     fn clear_tables(&mut self) -> Result<(), WalletError> {
+        self.client.execute("DELETE FROM goals", &[])?;
+        self.client.execute("DELETE FROM invoice_payments", &[])?;
+        self.client.execute("DELETE FROM links", &[])?;
+        self.client.execute("DELETE FROM invoices", &[])?;
+        self.client.execute("DELETE FROM snapshot_items", &[])?;
+        self.client.execute("DELETE FROM snapshots", &[])?;
+        self.client.execute("DELETE FROM period_locks", &[])?;
+        self.client.execute("DELETE FROM envelopes", &[])?;
+        self.client.execute("DELETE FROM fx_rates", &[])?;
+        self.client.execute("DELETE FROM ledger_kinds", &[])?;
+        self.client.execute("DELETE FROM ledger_policies", &[])?;
+        self.client.execute("DELETE FROM templates", &[])?;
         self.client.execute("DELETE FROM proceedings", &[])?;
         self.client.execute("DELETE FROM ledgers", &[])?;
-        println!("All data cleared from ledgers and proceedings tables.");
+        outln!("All data cleared from ledgers and proceedings tables.");
         Ok(())
     }
 }
 
+#[derive(Subcommand)]
+enum ReportCommand {
+    /// Interest and fees charged on each liability ledger, month by month.
+    /// Counts postings from a LIABILITY ledger to an INTEREST or FEE ledger.
+    Interest {
+        #[arg(long, help = "Year to report on (defaults to the current year)")]
+        year: Option<i32>,
+        #[command(flatten)]
+        fx: FxArgs,
+    },
+    /// Tax paid per ledger, quarter by quarter, for input-credit claims
+    Tax {
+        #[arg(long, help = "Year to report on (defaults to the current year)")]
+        year: Option<i32>,
+        #[command(flatten)]
+        fx: FxArgs,
+    },
+}
+
+#[derive(Subcommand)]
+enum LedgerCommand {
+    /// Change a ledger's kind from a date onwards, keeping earlier
+    /// transactions reported under the kind they had at the time
+    Rekind {
+        code: String,
+        kind: String,
+        #[arg(long, help = "First day the new kind applies")]
+        effective: String,
+    },
+    /// Show or set what every spend on a ledger must carry; setting
+    /// replaces the whole policy
+    Policy {
+        code: String,
+        #[arg(long)]
+        require_payee: bool,
+        #[arg(long)]
+        require_project: bool,
+        #[arg(long, help = "Tag every spend needs, e.g. client for client:acme")]
+        require_tag: Option<String>,
+        #[arg(long, help = "Narration used when a spend gives none")]
+        default_narration: Option<String>,
+        #[arg(long, help = "Remove the policy", conflicts_with_all = ["require_payee", "require_project", "require_tag", "default_narration"])]
+        clear: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoalCommand {
+    /// Add a savings goal tracked against a ledger's balance
+    Add {
+        name: String,
+        target: f64,
+        #[arg(long, help = "Date the goal should be reached by")]
+        by: Option<String>,
+        #[arg(long, help = "Ledger whose balance counts towards the goal")]
+        ledger: String,
+    },
+    /// Progress, projected completion and warnings for every goal
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List profiles, marking the selected one
+    List,
+    /// Create a profile with its own, empty set of tables
+    Create { name: String },
+    /// Delete a profile and everything recorded in it
+    Delete { name: String },
+}
+
+#[derive(Subcommand)]
+enum FxCommand {
+    /// Record what one unit of a currency cost in INR on a day
+    Set {
+        currency: String,
+        rate: f64,
+        #[arg(long, help = "Day the rate applies to (defaults to today)")]
+        date: Option<String>,
+    },
+    List {
+        currency: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateCommand {
+    /// Save a spend to record again with `t NAME`
+    Add {
+        name: String,
+        patron: String,
+        outlay: String,
+        amount: f64,
+        #[arg(help = "Optional when a ledger has a default narration")]
+        narration: Option<String>,
+    },
+    List,
+    Remove {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum InvoiceCommand {
+    /// Record an invoice raised against a client ledger
+    Add {
+        client: String,
+        amount: f64,
+        #[arg(long, help = "Payment due date (defaults to 30 days after issue)")]
+        due: Option<String>,
+        #[arg(long, help = "Issue date (defaults to today)")]
+        issued: Option<String>,
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Apply a payment received from the client to an invoice
+    Link {
+        invoice: i32,
+        transaction: i32,
+        #[arg(
+            long,
+            help = "Part of the payment to apply (defaults to as much as settles the invoice)"
+        )]
+        amount: Option<f64>,
+    },
+    /// Outstanding invoices by client, bucketed by days overdue
+    Aging {
+        #[arg(long, help = "Day to age invoices at (defaults to today)")]
+        as_of: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum SnapshotCommand {
+    /// Store a ledger's current balance and the transactions behind it
+    Take { code: String },
+    /// List the transactions that account for the change in a ledger's
+    /// balance since its last snapshot on or before a date
+    Diff { code: String, date: String },
+}
+
+#[derive(Subcommand)]
+enum MonthCommand {
+    /// Guided zero-based close: assign unbudgeted income, carry envelopes
+    /// forward, sweep the remainder and lock the month
+    Close {
+        #[arg(
+            long,
+            help = "Month to close (e.g. 'apr', '2025-04'); defaults to the current month"
+        )]
+        month: Option<String>,
+    },
+}
+
 // CLI commands
 #[derive(Parser)]
 #[command(name = "wallet")]
 #[command(about = "A simple wallet management CLI", long_about = None)]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        visible_alias = "non-interactive",
+        help = "Skip prompts: yes/no questions are answered yes and steps that need a choice are skipped"
+    )]
+    yes: bool,
+    #[arg(
+        short,
+        long,
+        global = true,
+        help = "Suppress normal output; errors are still printed"
+    )]
+    quiet: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "Wallet to work in (defaults to SPENDLOG_PROFILE, then 'default')"
+    )]
+    profile: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "Use one database connection and run queries one at a time, for small machines (also SPENDLOG_LOW_MEMORY=1)"
+    )]
+    low_memory: bool,
+    #[arg(
+        long,
+        global = true,
+        value_name = "ZONE",
+        help = "Time zone days are counted in, e.g. Asia/Kolkata (defaults to SPENDLOG_TZ, then the database server's)"
+    )]
+    tz: Option<String>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -874,17 +1470,61 @@ enum Commands {
         sort: String,
         kind: String,
     },
-    /// Add a new spending entry
+    /// Add a new spending entry; with no arguments, prompts for one with
+    /// saved templates offered as presets
     Spend {
-        patron: String,
-        outlay: String,
-        amount: f64,
-        narration: String,
+        #[arg(requires = "outlay")]
+        patron: Option<String>,
+        #[arg(requires = "amount")]
+        outlay: Option<String>,
+        amount: Option<f64>,
+        #[arg(help = "Optional when a ledger has a default narration")]
+        narration: Option<String>,
+        #[arg(long)]
+        date: Option<String>,
+        #[arg(long, help = "Who was paid")]
+        payee: Option<String>,
+        #[arg(long, help = "Project the spend is attributed to")]
+        project: Option<String>,
+        #[arg(long = "tag", help = "Tag such as client:acme (repeatable)")]
+        tags: Vec<String>,
+        #[arg(long, help = "Tax rate included in the amount, e.g. 18%")]
+        tax: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "tax",
+            help = "Tax included in the amount, as an absolute figure"
+        )]
+        tax_amount: Option<f64>,
+        #[arg(
+            long,
+            help = "Post-dated or pending: kept out of balances until it clears on this date and is confirmed"
+        )]
+        clears_on: Option<String>,
+        #[arg(
+            long,
+            help = "Pending with no known clearing date (e.g. a card authorisation)"
+        )]
+        pending: bool,
+    },
+    /// Saved spends for quick entry
+    Template {
+        #[command(subcommand)]
+        command: TemplateCommand,
+    },
+    /// Record a spend from a saved template
+    T {
+        name: String,
+        #[arg(long, help = "Amount to use instead of the template's")]
+        amount: Option<f64>,
         #[arg(long)]
         date: Option<String>,
     },
     /// Generate a spending report
+    #[command(args_conflicts_with_subcommands = true)]
     Report {
+        #[command(subcommand)]
+        command: Option<ReportCommand>,
         #[arg(value_enum)]
         period: Option<ReportPeriod>,
         #[arg(long)]
@@ -893,6 +1533,10 @@ enum Commands {
         from: Option<String>,
         #[arg(long)]
         to: Option<String>,
+        #[arg(long, value_enum, help = "Total by period, ledger or ledger kind")]
+        group_by: Option<GroupBy>,
+        #[command(flatten)]
+        fx: FxArgs,
     },
     // SummaryReport {
     //     #[arg(value_enum, default_value_t = ReportPeriod::All)]
@@ -908,6 +1552,10 @@ enum Commands {
         from: Option<String>,
         #[arg(long)]
         to: Option<String>,
+        #[command(flatten)]
+        paging: PageArgs,
+        #[command(flatten)]
+        fx: FxArgs,
     },
     /// List all ledgers
     Calendar {
@@ -917,18 +1565,293 @@ enum Commands {
         month: Option<String>,
         #[arg(help = "Daily spending cap (e.g., '500')")]
         cap: Option<String>,
+        #[command(flatten)]
+        fx: FxArgs,
     },
     ListLedgers,
-    Last,
+    /// Render a shareable PNG summary card for a month
+    Card {
+        #[arg(
+            long,
+            help = "Month to summarise (e.g. 'apr'); defaults to the current month"
+        )]
+        month: Option<String>,
+        #[arg(long, default_value = "card.png", help = "Where to write the PNG")]
+        output: String,
+        #[arg(
+            long,
+            help = "Daily cap for the streak (default: counts no-spend days)"
+        )]
+        cap: Option<f64>,
+        #[arg(long, help = "Show amounts as percentages instead of absolute values")]
+        percent: bool,
+    },
+    /// Compile a month's summary, ledger totals, calendar and transactions
+    /// into one statement for emailing or archiving
+    Statement {
+        #[arg(
+            long,
+            help = "Month (e.g. 'april', '2025-04'); defaults to the current month"
+        )]
+        month: Option<String>,
+        #[arg(long, value_enum, default_value_t = StatementFormat::Text)]
+        format: StatementFormat,
+        #[arg(long, help = "File to write the statement to (default: stdout)")]
+        out: Option<String>,
+    },
+    /// Annual review: totals and category trends against the prior year,
+    /// biggest purchases, savings rate by month and goal progress
+    Review {
+        #[arg(help = "Year to review (e.g. 2024)")]
+        year: i32,
+        #[arg(long, value_enum, default_value_t = ReviewFormat::Text)]
+        format: ReviewFormat,
+        #[arg(
+            long,
+            help = "File to write the review to (default: stdout, or review-YEAR.pdf for pdf)"
+        )]
+        out: Option<String>,
+    },
+    /// Ledger maintenance
+    Ledger {
+        #[command(subcommand)]
+        command: LedgerCommand,
+    },
+    /// Savings goals
+    Goal {
+        #[command(subcommand)]
+        command: GoalCommand,
+    },
+    /// Client invoices, payments against them and receivables aging
+    Invoice {
+        #[command(subcommand)]
+        command: InvoiceCommand,
+    },
+    /// Separate wallets (e.g. personal, household) in one database
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Exchange rates used by the reports' --also-in column
+    Fx {
+        #[command(subcommand)]
+        command: FxCommand,
+    },
+    /// Balance snapshots for tracking down reconciliation drift
+    Snapshot {
+        #[command(subcommand)]
+        command: SnapshotCommand,
+    },
+    /// Month-end budgeting workflows
+    Month {
+        #[command(subcommand)]
+        command: MonthCommand,
+    },
+    /// Import spends from a CSV file (date,patron,outlay,amount,narration[,payee,project,tags])
+    Import {
+        file: String,
+    },
+    /// Find transactions recorded twice and remove the extra copies
+    Dedup {
+        #[arg(
+            long,
+            help = "Minutes apart two copies may be (defaults to SPENDLOG_DEDUP_WINDOW, 60)"
+        )]
+        window: Option<i64>,
+    },
+    /// Erase what identifies a payee or ledger, after a preview, keeping
+    /// every balance the same
+    Forget {
+        #[arg(
+            long,
+            required_unless_present = "ledger",
+            conflicts_with = "ledger",
+            help = "Payee to forget (matched case-insensitively)"
+        )]
+        payee: Option<String>,
+        #[arg(long, value_name = "CODE", help = "Ledger to forget")]
+        ledger: Option<String>,
+        #[arg(
+            long,
+            help = "Delete the transactions and replace them with consolidated entries instead of anonymizing them"
+        )]
+        remove: bool,
+    },
+    Last {
+        #[command(flatten)]
+        paging: PageArgs,
+    },
+    /// Today, this month, budget and recent transactions on one screen
+    Dashboard,
+    /// Serve an HTTP/JSON API for recording spends and reading reports
+    Serve {
+        #[arg(long, default_value = "127.0.0.1:8080", help = "Address to listen on")]
+        listen: String,
+        #[arg(
+            long,
+            help = "Require this bearer token on every request (defaults to SPENDLOG_API_TOKEN)"
+        )]
+        token: Option<String>,
+    },
+    /// Full-screen display that cycles through the dashboard panels
+    Tui {
+        #[arg(
+            long,
+            required = true,
+            help = "Read-only display with no input handling, for a wall-mounted screen"
+        )]
+        kiosk: bool,
+        #[arg(
+            long,
+            default_value_t = 60,
+            value_parser = clap::value_parser!(u64).range(1..),
+            help = "Seconds each panel is shown"
+        )]
+        refresh: u64,
+    },
+    /// Try spendlog on made-up data kept in memory, without a database
+    Demo {
+        #[arg(
+            trailing_var_arg = true,
+            allow_hyphen_values = true,
+            help = "Command to run against the demo data (e.g. 'report month'); starts a demo shell if omitted"
+        )]
+        args: Vec<String>,
+    },
+    /// Link two transactions the same money passed through, e.g. an expense
+    /// and its reimbursement or the two legs of a transfer
+    Link {
+        #[arg(help = "Transaction the money came from")]
+        from: i32,
+        #[arg(help = "Transaction it went on in")]
+        to: i32,
+        #[arg(long, value_enum, required_unless_present = "remove")]
+        kind: Option<LinkKind>,
+        #[arg(long, conflicts_with = "kind", help = "Remove the link instead")]
+        remove: bool,
+    },
+    /// Count and total of matching transactions, without a full report
+    Sum {
+        #[arg(
+            long,
+            help = "Only transactions on this ledger, totalled as debits minus credits"
+        )]
+        ledger: Option<String>,
+        #[arg(long, help = "First day to include (e.g. 2025-04-01, 'apr')")]
+        from: Option<String>,
+        #[arg(long, help = "Last day to include")]
+        to: Option<String>,
+        #[arg(
+            long,
+            value_name = "TEXT",
+            help = "Narration containing TEXT, ignoring case (or a LIKE pattern with % or _)"
+        )]
+        narration_like: Option<String>,
+        #[arg(long, help = "One line per week (weeks start on Monday)")]
+        by_week: bool,
+    },
+    /// Follow a transaction's links: where its money came from, where it
+    /// went next and where it ended up
+    Trace {
+        id: i32,
+    },
+    /// Show every field of a single transaction
+    Show {
+        id: i32,
+    },
+    /// Confirm a pending or post-dated transaction so it counts in balances
+    ClearItem {
+        id: i32,
+        #[arg(long, help = "Day it cleared (defaults to today)")]
+        date: Option<String>,
+    },
+    /// Match a ledger against a bank or card statement, mark the entries
+    /// on it as cleared and adjust for any difference left over
+    Reconcile {
+        code: String,
+        #[arg(
+            long,
+            allow_negative_numbers = true,
+            help = "Closing balance on the statement, as debits minus credits (negative for an amount owed)"
+        )]
+        statement_balance: f64,
+        #[arg(long, help = "Statement date (defaults to today)")]
+        as_of: Option<String>,
+        #[arg(
+            long,
+            value_name = "CODE",
+            help = "Ledger to record the adjustment against (prompted for if omitted)"
+        )]
+        adjust_with: Option<String>,
+    },
     DbSetup,
+    /// Print a completion script; e.g. `source <(spendlog completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Ledger codes one per line, for the completion scripts
+    #[command(name = "_complete-ledgers", hide = true)]
+    CompleteLedgers,
+    /// Upgrade a v1 database (floating-point amounts) to the v2 schema with
+    /// decimal amounts, currencies, uuids and effective dates
+    Upgrade {
+        #[arg(
+            long,
+            help = "CSV file to back proceedings up to (default: spendlog-v1-backup-<timestamp>.csv)"
+        )]
+        backup: Option<String>,
+        #[arg(
+            long,
+            default_value = "INR",
+            help = "Currency assigned to existing proceedings"
+        )]
+        currency: String,
+        #[arg(
+            long,
+            default_value_t = 0.01,
+            help = "Largest change in any ledger's balance accepted after rounding"
+        )]
+        tolerance: f64,
+        #[arg(
+            long,
+            help = "Run every step, then roll back; no CSV backup is written"
+        )]
+        dry_run: bool,
+    },
     Clear,
 }
 
-fn main() -> Result<(), WalletError> {
+fn main() -> ExitCode {
     let cli = Cli::parse();
+    output::set_quiet(cli.quiet);
+    output::set_interactive(!cli.yes);
+
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), WalletError> {
+    // Completion scripts are generated without a database connection
+    if let Commands::Completions { shell } = cli.command {
+        out!("{}", completions::generate(shell, &mut Cli::command()));
+        return Ok(());
+    }
+    // The demo keeps its data in memory and never connects
+    if let Commands::Demo { args } = &cli.command {
+        return demo::demo(args);
+    }
 
     // Initialize the database
-    let mut db = WalletDB::new()?;
+    let mut db = WalletDB::new(cli.profile.as_deref(), cli.low_memory, cli.tz.as_deref())?;
+    if !matches!(cli.command, Commands::Profile { .. }) {
+        db.ensure_profile()?;
+    }
 
     match cli.command {
         Commands::AddLedger {
@@ -950,60 +1873,137 @@ fn main() -> Result<(), WalletError> {
             amount,
             narration,
             date,
+            payee,
+            project,
+            tags,
+            tax,
+            tax_amount,
+            clears_on,
+            pending,
         } => {
-            let created_at = if let Some(date_str) = date {
-                // Parse the date string (e.g., "2025-04-20") into a NaiveDate
-                let naive_date =
-                    NaiveDate::parse_from_str(&date_str, "%Y-%m-%d").map_err(|_| {
-                        WalletError::InvalidDate(format!(
-                            "Invalid date format: {}. Use YYYY-MM-DD",
-                            date_str
-                        ))
-                    })?;
-                // Convert to NaiveDateTime by setting time to 00:00:00
-                Some(naive_date.and_hms_opt(0, 0, 0).unwrap())
-            } else {
-                None
+            let today = dates::today();
+            let created_at = date
+                .map(|date_str| dates::parse_day(&date_str, today))
+                .transpose()?
+                .map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+            let clears_on = clears_on
+                .map(|date_str| dates::parse_upcoming_day(&date_str, today))
+                .transpose()?;
+            let tax_rate = tax.map(|rate| tax::parse_rate(&rate)).transpose()?;
+            let mut entry = SpendEntry {
+                patron: patron.unwrap_or_default(),
+                outlay: outlay.unwrap_or_default(),
+                amount: amount.unwrap_or_default(),
+                narration: narration.unwrap_or_default(),
+                created_at,
+                pending: pending || clears_on.is_some(),
+                clears_on,
+                payee,
+                project,
+                tags,
+                tax_amount,
+                tax_rate,
+                cleared_at: None,
             };
-            db.proceed_spend(&patron, &outlay, amount, &narration, created_at)
+            if entry.patron.is_empty() {
+                if !output::interactive() {
+                    let mut command = Cli::command().bin_name(env!("CARGO_PKG_NAME"));
+                    command.build();
+                    command
+                        .find_subcommand_mut("spend")
+                        .unwrap()
+                        .error(
+                            ErrorKind::MissingRequiredArgument,
+                            "spend needs PATRON, OUTLAY and AMOUNT when prompts are off",
+                        )
+                        .exit();
+                }
+                db.prompt_spend(&mut entry)?;
+            }
+            if let Some(rate) = tax_rate {
+                entry.tax_amount = Some(tax::included_tax(entry.amount, rate));
+            }
+            db.record_spend(entry).map_err(|e| {
+                eprintln!("Failed to record spending: {}", e);
+                e
+            })?;
+        }
+        Commands::Template {
+            command:
+                TemplateCommand::Add {
+                    name,
+                    patron,
+                    outlay,
+                    amount,
+                    narration,
+                },
+        } => {
+            db.add_spend_template(&name, &patron, &outlay, amount, narration.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to save template: {}", e);
+                    e
+                })?;
+        }
+        Commands::Template {
+            command: TemplateCommand::List,
+        } => {
+            db.list_spend_templates().map_err(|e| {
+                eprintln!("Failed to list templates: {}", e);
+                e
+            })?;
+        }
+        Commands::Template {
+            command: TemplateCommand::Remove { name },
+        } => {
+            db.remove_spend_template(&name).map_err(|e| {
+                eprintln!("Failed to remove template: {}", e);
+                e
+            })?;
+        }
+        Commands::T { name, amount, date } => {
+            let created_at = date
+                .map(|date_str| dates::parse_day(&date_str, dates::today()))
+                .transpose()?
+                .map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+            db.spend_from_template(&name, amount, created_at)
                 .map_err(|e| {
                     eprintln!("Failed to record spending: {}", e);
                     e
                 })?;
         }
         Commands::Report {
+            command: Some(ReportCommand::Interest { year, fx }),
+            ..
+        } => {
+            db.generate_interest_report(year, &fx).map_err(|e| {
+                eprintln!("Failed to generate interest report: {}", e);
+                e
+            })?;
+        }
+        Commands::Report {
+            command: Some(ReportCommand::Tax { year, fx }),
+            ..
+        } => {
+            db.generate_tax_report(year, &fx).map_err(|e| {
+                eprintln!("Failed to generate tax report: {}", e);
+                e
+            })?;
+        }
+        Commands::Report {
+            command: None,
             period,
             date,
             from,
             to,
+            group_by,
+            fx,
         } => {
-            let period = match (period, date, from, to) {
-                (Some(p), None, None, None) => p,
-                (None, Some(date), None, None) => ReportPeriod::Date(date),
-                (None, None, Some(from), Some(to)) => ReportPeriod::FromTo { from, to },
-                (None, None, None, None) => ReportPeriod::All, // Default to All if nothing is specified
-                (Some(_), Some(_), _, _) => {
-                    return Err(WalletError::InvalidDate(
-                        "Cannot specify both a period and a date. Use either 'spendlog report <period>' or 'spendlog report --date <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-                (Some(_), _, Some(_), Some(_)) => {
-                    return Err(WalletError::InvalidDate(
-                        "Cannot specify both a period and a date range. Use either 'spendlog report <period>' or 'spendlog report --from <YYYY-MM-DD> --to <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-                (None, None, Some(_), None) | (None, None, None, Some(_)) => {
-                    return Err(WalletError::InvalidDate(
-                        "Must specify both --from and --to dates for a date range.".to_string(),
-                    ));
-                }
-                _ => {
-                    return Err(WalletError::InvalidDate(
-                        "Invalid combination of arguments. Use 'spendlog report <period>', 'spendlog report --date <YYYY-MM-DD>', or 'spendlog report --from <YYYY-MM-DD> --to <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-            };
-            db.generate_spending_report(period).map_err(|e| {
+            let period = ReportPeriod::from_args("report", period, date, from, to)?;
+            match group_by {
+                Some(group_by) => db.generate_grouped_report(period, group_by, &fx),
+                None => db.generate_spending_report(period, &fx),
+            }
+            .map_err(|e| {
                 eprintln!("Failed to generate report: {}", e);
                 e
             })?;
@@ -1014,46 +2014,25 @@ fn main() -> Result<(), WalletError> {
             date,
             from,
             to,
+            paging,
+            fx,
         } => {
-            let period = match (period, date, from, to) {
-                (Some(p), None, None, None) => p,
-                (None, Some(date), None, None) => ReportPeriod::Date(date),
-                (None, None, Some(from), Some(to)) => ReportPeriod::FromTo { from, to },
-                (None, None, None, None) => ReportPeriod::All, // Default to All if nothing is specified
-                (Some(_), Some(_), _, _) => {
-                    return Err(WalletError::InvalidDate(
-                        "Cannot specify both a period and a date. Use either 'spendlog ledger-report <code> <period>' or 'spendlog ledger-report <code> --date <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-                (Some(_), _, Some(_), Some(_)) => {
-                    return Err(WalletError::InvalidDate(
-                        "Cannot specify both a period and a date range. Use either 'spendlog ledger-report <code> <period>' or 'spendlog ledger-report <code> --from <YYYY-MM-DD> --to <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-                (None, None, Some(_), None) | (None, None, None, Some(_)) => {
-                    return Err(WalletError::InvalidDate(
-                        "Must specify both --from and --to dates for a date range.".to_string(),
-                    ));
-                }
-                _ => {
-                    return Err(WalletError::InvalidDate(
-                        "Invalid combination of arguments. Use 'spendlog ledger-report <code> <period>', 'spendlog ledger-report <code> --date <YYYY-MM-DD>', or 'spendlog ledger-report <code> --from <YYYY-MM-DD> --to <YYYY-MM-DD>'.".to_string(),
-                    ));
-                }
-            };
-            db.generate_ledger_report(&code, period).map_err(|e| {
-                eprintln!("Failed to generate ledger report: {}", e);
-                e
-            })?;
+            let command = format!("ledger-report {}", code);
+            let period = ReportPeriod::from_args(&command, period, date, from, to)?;
+            db.generate_ledger_report(&code, period, &paging, &fx)
+                .map_err(|e| {
+                    eprintln!("Failed to generate ledger report: {}", e);
+                    e
+                })?;
         }
 
         Commands::ListLedgers => {
-            db.list_ledgers().map_err(|e| {
+            store::list_ledgers(&mut db).map_err(|e| {
                 eprintln!("Failed to list ledgers: {}", e);
                 e
             })?;
         }
-        Commands::Calendar { month, cap } => {
+        Commands::Calendar { month, cap, fx } => {
             // Determine if the month argument is actually a cap value
             let (month_arg, cap_value) = match (month.clone(), cap) {
                 (Some(m), Some(c)) => {
@@ -1101,27 +2080,376 @@ fn main() -> Result<(), WalletError> {
                 (None, None) => (None, None),
             };
 
-            db.generate_calendar_report(month_arg.as_deref(), cap_value)
+            db.generate_calendar_report(month_arg.as_deref(), cap_value, &fx)
                 .map_err(|e| {
                     eprintln!("Failed to generate calendar report: {}", e);
                     e
                 })?;
         }
-        Commands::Last => {
-            db.generate_recent_transactions_report().map_err(|e| {
+        Commands::Card {
+            month,
+            output,
+            cap,
+            percent,
+        } => {
+            db.generate_card(month.as_deref(), cap, percent, &output)
+                .map_err(|e| {
+                    eprintln!("Failed to generate report card: {}", e);
+                    e
+                })?;
+        }
+        Commands::Statement { month, format, out } => {
+            db.generate_statement(month.as_deref(), format, out.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to generate statement: {}", e);
+                    e
+                })?;
+        }
+        Commands::Review { year, format, out } => {
+            db.generate_review(year, format, out.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to generate annual review: {}", e);
+                    e
+                })?;
+        }
+        Commands::Ledger {
+            command:
+                LedgerCommand::Rekind {
+                    code,
+                    kind,
+                    effective,
+                },
+        } => {
+            let effective = dates::parse_day(&effective, dates::today())?;
+            db.rekind_ledger(&code, &kind, effective).map_err(|e| {
+                eprintln!("Failed to change ledger kind: {}", e);
+                e
+            })?;
+        }
+        Commands::Ledger {
+            command:
+                LedgerCommand::Policy {
+                    code,
+                    require_payee,
+                    require_project,
+                    require_tag,
+                    default_narration,
+                    clear,
+                },
+        } => {
+            let result = if clear {
+                db.clear_ledger_policy(&code)
+            } else {
+                db.set_ledger_policy(
+                    &code,
+                    require_payee,
+                    require_project,
+                    require_tag.as_deref(),
+                    default_narration.as_deref(),
+                )
+            };
+            result.map_err(|e| {
+                eprintln!("Failed to update ledger policy: {}", e);
+                e
+            })?;
+        }
+        Commands::Goal {
+            command:
+                GoalCommand::Add {
+                    name,
+                    target,
+                    by,
+                    ledger,
+                },
+        } => {
+            let by = by
+                .map(|by| dates::parse_day(&by, dates::today()))
+                .transpose()?;
+            db.add_goal(&name, target, by, &ledger).map_err(|e| {
+                eprintln!("Failed to add goal: {}", e);
+                e
+            })?;
+        }
+        Commands::Goal {
+            command: GoalCommand::Status,
+        } => {
+            db.goal_status().map_err(|e| {
+                eprintln!("Failed to show goal status: {}", e);
+                e
+            })?;
+        }
+        Commands::Invoice {
+            command:
+                InvoiceCommand::Add {
+                    client,
+                    amount,
+                    due,
+                    issued,
+                    note,
+                },
+        } => {
+            let today = dates::today();
+            let due = due
+                .map(|due| dates::parse_upcoming_day(&due, today))
+                .transpose()?;
+            let issued = issued
+                .map(|issued| dates::parse_day(&issued, today))
+                .transpose()?
+                .unwrap_or(today);
+            db.add_invoice(&client, amount, due, issued, note.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to add invoice: {}", e);
+                    e
+                })?;
+        }
+        Commands::Invoice {
+            command:
+                InvoiceCommand::Link {
+                    invoice,
+                    transaction,
+                    amount,
+                },
+        } => {
+            db.link_payment(invoice, transaction, amount).map_err(|e| {
+                eprintln!("Failed to link payment: {}", e);
+                e
+            })?;
+        }
+        Commands::Invoice {
+            command: InvoiceCommand::Aging { as_of },
+        } => {
+            let today = dates::today();
+            let as_of = as_of
+                .map(|as_of| dates::parse_day(&as_of, today))
+                .transpose()?
+                .unwrap_or(today);
+            db.invoice_aging(as_of).map_err(|e| {
+                eprintln!("Failed to build aging report: {}", e);
+                e
+            })?;
+        }
+        Commands::Profile {
+            command: ProfileCommand::List,
+        } => {
+            db.list_profiles().map_err(|e| {
+                eprintln!("Failed to list profiles: {}", e);
+                e
+            })?;
+        }
+        Commands::Profile {
+            command: ProfileCommand::Create { name },
+        } => {
+            db.create_profile(&name).map_err(|e| {
+                eprintln!("Failed to create profile: {}", e);
+                e
+            })?;
+        }
+        Commands::Profile {
+            command: ProfileCommand::Delete { name },
+        } => {
+            db.delete_profile(&name).map_err(|e| {
+                eprintln!("Failed to delete profile: {}", e);
+                e
+            })?;
+        }
+        Commands::Fx {
+            command:
+                FxCommand::Set {
+                    currency,
+                    rate,
+                    date,
+                },
+        } => {
+            let today = dates::today();
+            let on = date
+                .map(|date_str| dates::parse_day(&date_str, today))
+                .transpose()?
+                .unwrap_or(today);
+            db.set_fx_rate(&currency, rate, on).map_err(|e| {
+                eprintln!("Failed to set exchange rate: {}", e);
+                e
+            })?;
+        }
+        Commands::Fx {
+            command: FxCommand::List { currency },
+        } => {
+            db.list_fx_rates(currency.as_deref()).map_err(|e| {
+                eprintln!("Failed to list exchange rates: {}", e);
+                e
+            })?;
+        }
+        Commands::Snapshot {
+            command: SnapshotCommand::Take { code },
+        } => {
+            db.take_snapshot(&code).map_err(|e| {
+                eprintln!("Failed to take snapshot: {}", e);
+                e
+            })?;
+        }
+        Commands::Snapshot {
+            command: SnapshotCommand::Diff { code, date },
+        } => {
+            let day = dates::parse_day(&date, dates::today())?;
+            db.diff_snapshot(&code, day).map_err(|e| {
+                eprintln!("Failed to diff snapshot: {}", e);
+                e
+            })?;
+        }
+        Commands::Month {
+            command: MonthCommand::Close { month },
+        } => {
+            db.close_month(month.as_deref()).map_err(|e| {
+                eprintln!("Failed to close month: {}", e);
+                e
+            })?;
+        }
+        Commands::Import { file } => {
+            db.import_csv(&file).map_err(|e| {
+                eprintln!("Failed to import {}: {}", file, e);
+                e
+            })?;
+        }
+        Commands::Dedup { window } => {
+            db.dedup(window).map_err(|e| {
+                eprintln!("Failed to find duplicates: {}", e);
+                e
+            })?;
+        }
+        Commands::Forget {
+            payee,
+            ledger,
+            remove,
+        } => {
+            db.forget(payee.as_deref(), ledger.as_deref(), remove)
+                .map_err(|e| {
+                    eprintln!("Failed to forget: {}", e);
+                    e
+                })?;
+        }
+        Commands::Last { paging } => {
+            store::recent_report(&mut db, &paging).map_err(|e| {
                 eprintln!("Failed to generate recent transactions report: {}", e);
                 e
             })?;
         }
+        Commands::Dashboard => {
+            db.dashboard().map_err(|e| {
+                eprintln!("Failed to build dashboard: {}", e);
+                e
+            })?;
+        }
+        Commands::Serve { listen, token } => {
+            let token = token.or(Config::load().api_token);
+            db.serve(&listen, token.as_deref()).map_err(|e| {
+                eprintln!("Failed to serve API: {}", e);
+                e
+            })?;
+        }
+        Commands::Tui { kiosk: _, refresh } => {
+            db.kiosk(std::time::Duration::from_secs(refresh))
+                .map_err(|e| {
+                    eprintln!("Failed to run kiosk display: {}", e);
+                    e
+                })?;
+        }
+        Commands::Link {
+            from,
+            to,
+            kind,
+            remove: _,
+        } => {
+            match kind {
+                Some(kind) => db.link(from, to, kind),
+                None => db.unlink(from, to),
+            }
+            .map_err(|e| {
+                eprintln!("Failed to update link: {}", e);
+                e
+            })?;
+        }
+        Commands::Sum {
+            ledger,
+            from,
+            to,
+            narration_like,
+            by_week,
+        } => {
+            db.quick_sum(
+                ledger.as_deref(),
+                from.as_deref(),
+                to.as_deref(),
+                narration_like.as_deref(),
+                by_week,
+            )
+            .map_err(|e| {
+                eprintln!("Failed to sum transactions: {}", e);
+                e
+            })?;
+        }
+        Commands::Trace { id } => {
+            db.trace(id).map_err(|e| {
+                eprintln!("Failed to trace transaction: {}", e);
+                e
+            })?;
+        }
+        Commands::Show { id } => {
+            db.show_proceeding(id).map_err(|e| {
+                eprintln!("Failed to show transaction: {}", e);
+                e
+            })?;
+        }
+        Commands::ClearItem { id, date } => {
+            let on = date
+                .map(|date_str| dates::parse_day(&date_str, dates::today()))
+                .transpose()?;
+            db.clear_item(id, on).map_err(|e| {
+                eprintln!("Failed to clear transaction: {}", e);
+                e
+            })?;
+        }
+        Commands::Reconcile {
+            code,
+            statement_balance,
+            as_of,
+            adjust_with,
+        } => {
+            let today = dates::today();
+            let as_of = as_of
+                .map(|date_str| dates::parse_day(&date_str, today))
+                .transpose()?
+                .unwrap_or(today);
+            db.reconcile(&code, statement_balance, as_of, adjust_with.as_deref())
+                .map_err(|e| {
+                    eprintln!("Failed to reconcile {}: {}", code, e);
+                    e
+                })?;
+        }
+        Commands::Completions { .. } | Commands::Demo { .. } => {}
+        Commands::CompleteLedgers => {
+            for code in db.ledger_codes()? {
+                outln!("{}", code);
+            }
+        }
         Commands::DbSetup => {
             db.setup_db()?;
         }
+        Commands::Upgrade {
+            backup,
+            currency,
+            tolerance,
+            dry_run,
+        } => {
+            db.upgrade(backup.as_deref(), &currency, tolerance, dry_run)
+                .map_err(|e| {
+                    eprintln!("Failed to upgrade database: {}", e);
+                    e
+                })?;
+        }
         Commands::Clear => {
-            let confirmed = Confirm::with_theme(&ColorfulTheme::default())
-                .with_prompt("Are you sure you want to delete all data from the database? This action cannot be undone.")
-                .default(false)
-                .interact()
-                .unwrap_or(false);
+            let confirmed = output::confirm(
+                "Are you sure you want to delete all data from the database? This action cannot be undone.",
+                false,
+            )?;
 
             if confirmed {
                 db.clear_tables().map_err(|e| {
@@ -1129,7 +2457,7 @@ fn main() -> Result<(), WalletError> {
                     e
                 })?;
             } else {
-                println!("Operation canceled. No data was deleted.");
+                outln!("Operation canceled. No data was deleted.");
             }
         }
     }
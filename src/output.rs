@@ -0,0 +1,55 @@
+// Process-wide output settings from the global CLI flags. Human-readable
+// output goes through `outln!`/`out!` so `--quiet` can silence it; errors
+// still go to stderr.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use dialoguer::{theme::ColorfulTheme, Confirm};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+static INTERACTIVE: AtomicBool = AtomicBool::new(true);
+
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+pub fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+// With `--yes` prompts are skipped: yes/no questions are answered yes and
+// steps that need a choice are left out
+pub fn set_interactive(interactive: bool) {
+    INTERACTIVE.store(interactive, Ordering::Relaxed);
+}
+
+pub fn interactive() -> bool {
+    INTERACTIVE.load(Ordering::Relaxed)
+}
+
+// Asks a yes/no question, or answers yes under `--yes`
+pub fn confirm(prompt: &str, default: bool) -> Result<bool, dialoguer::Error> {
+    if !interactive() {
+        return Ok(true);
+    }
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(default)
+        .interact()
+}
+
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        if !$crate::output::quiet() {
+            println!($($arg)*);
+        }
+    };
+}
+
+macro_rules! out {
+    ($($arg:tt)*) => {
+        if !$crate::output::quiet() {
+            print!($($arg)*);
+        }
+    };
+}
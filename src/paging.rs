@@ -0,0 +1,90 @@
+use clap::Args;
+use console::{Key, Term};
+
+use crate::output;
+
+// Limit/offset/pager flags shared by every command that lists proceedings.
+#[derive(Args, Clone, Debug, Default)]
+pub struct PageArgs {
+    #[arg(long, value_parser = clap::value_parser!(i64).range(1..), help = "Maximum number of rows to show")]
+    pub limit: Option<i64>,
+    #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(i64).range(0..), help = "Number of rows to skip")]
+    pub offset: i64,
+    #[arg(
+        long,
+        help = "Page through rows interactively (space: next page, enter: next row, q: quit)"
+    )]
+    pub pager: bool,
+}
+
+impl PageArgs {
+    // LIMIT/OFFSET clause for a listing query. Both values come from clap as
+    // range-checked integers, so they are safe to format into the SQL.
+    pub fn sql(&self, default_limit: Option<i64>) -> String {
+        match self.limit.or(default_limit) {
+            Some(limit) => format!("LIMIT {} OFFSET {}", limit, self.offset),
+            None => format!("OFFSET {}", self.offset),
+        }
+    }
+
+    // Describes the window being shown, e.g. "rows 11-20 of 134".
+    pub fn describe(&self, shown: usize, total: i64) -> String {
+        if shown == 0 {
+            return format!("no rows past offset {}", self.offset);
+        }
+        format!(
+            "rows {}-{} of {}",
+            self.offset + 1,
+            self.offset + shown as i64,
+            total
+        )
+    }
+
+    pub fn pager(&self) -> Pager {
+        let term = Term::stdout();
+        let interactive = self.pager && term.is_term() && output::interactive() && !output::quiet();
+        let page_size = (term.size().0 as usize).saturating_sub(4).max(1);
+        Pager {
+            term: interactive.then_some(term),
+            page_size,
+            remaining: page_size,
+            quit: false,
+        }
+    }
+}
+
+// Prints listing rows, pausing after each screenful when interactive.
+pub struct Pager {
+    term: Option<Term>,
+    page_size: usize,
+    remaining: usize,
+    quit: bool,
+}
+
+impl Pager {
+    // Prints a row, first waiting for a key press if the current page is
+    // full. Returns false once the user has quit so callers can stop early.
+    pub fn line(&mut self, line: &str) -> bool {
+        if self.quit {
+            return false;
+        }
+        if let Some(term) = &self.term {
+            if self.remaining == 0 {
+                let _ = term.write_str("-- more (space: next page, enter: next row, q: quit) --");
+                let key = term.read_key().unwrap_or(Key::Escape);
+                let _ = term.clear_line();
+                self.remaining = match key {
+                    Key::Char(' ') => self.page_size,
+                    Key::Enter => 1,
+                    _ => {
+                        self.quit = true;
+                        return false;
+                    }
+                };
+            }
+            self.remaining -= 1;
+        }
+        outln!("{}", line);
+        true
+    }
+}
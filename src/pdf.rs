@@ -0,0 +1,117 @@
+// Minimal PDF writer that sets plain text in Courier on A4 pages, enough to
+// export text reports without pulling in a typesetting stack.
+
+use std::fmt::Write;
+
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 40;
+const FONT_SIZE: u32 = 9;
+const LEADING: u32 = 11;
+const LINES_PER_PAGE: usize = ((PAGE_HEIGHT - 2 * MARGIN) / LEADING) as usize;
+
+// One object per page and one per page's content stream follow these
+const CATALOG: usize = 1;
+const PAGES: usize = 2;
+const FONT: usize = 3;
+
+pub fn from_text(text: &str) -> Vec<u8> {
+    let lines: Vec<&str> = text.lines().collect();
+    let pages: Vec<&[&str]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(LINES_PER_PAGE).collect()
+    };
+    let page_id = |i: usize| FONT + 1 + 2 * i;
+
+    let kids: Vec<String> = (0..pages.len())
+        .map(|i| format!("{} 0 R", page_id(i)))
+        .collect();
+    let mut objects: Vec<Vec<u8>> = vec![
+        format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES).into_bytes(),
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            pages.len()
+        )
+        .into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+    for (i, page) in pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] \
+                 /Resources << /Font << /F1 {} 0 R >> >> /Contents {} 0 R >>",
+                PAGES,
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                FONT,
+                page_id(i) + 1
+            )
+            .into_bytes(),
+        );
+        let content = page_content(page);
+        let mut stream = format!("<< /Length {} >>\nstream\n", content.len()).into_bytes();
+        stream.extend_from_slice(&content);
+        stream.extend_from_slice(b"\nendstream");
+        objects.push(stream);
+    }
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(table, "{:010} 00000 n ", offset);
+    }
+    let _ = write!(
+        table,
+        "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        CATALOG,
+        xref
+    );
+    pdf.extend_from_slice(table.as_bytes());
+    pdf
+}
+
+fn page_content(lines: &[&str]) -> Vec<u8> {
+    let mut content = format!(
+        "BT\n/F1 {} Tf\n{} TL\n{} {} Td\n",
+        FONT_SIZE,
+        LEADING,
+        MARGIN,
+        PAGE_HEIGHT - MARGIN - FONT_SIZE
+    )
+    .into_bytes();
+    for line in lines {
+        content.push(b'(');
+        content.extend(encode(line));
+        content.extend_from_slice(b") Tj T*\n");
+    }
+    content.extend_from_slice(b"ET");
+    content
+}
+
+// Latin-1 text as a PDF string body; anything the font cannot show becomes '?'
+fn encode(line: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(line.len());
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                bytes.push(b'\\');
+                bytes.push(c as u8);
+            }
+            '\t' => bytes.push(b' '),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => bytes.push(c as u8),
+            _ => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
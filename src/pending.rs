@@ -0,0 +1,109 @@
+use chrono::NaiveDate;
+use colored::Colorize;
+
+use crate::{dates, WalletDB, WalletError};
+
+impl WalletDB {
+    // Post-dated and pending items touching the ledger. They stay out of
+    // `balance` until confirmed with clear-item.
+    pub(crate) fn print_uncleared(
+        &mut self,
+        ledger_id: i32,
+        balance: f64,
+    ) -> Result<(), WalletError> {
+        let rows = self.client.query(
+            "
+            SELECT p.id, p.clears_on,
+                   CASE
+                       WHEN p.cr_from = $1 THEN (SELECT code FROM ledgers WHERE id = p.db_to)
+                       ELSE (SELECT code FROM ledgers WHERE id = p.cr_from)
+                   END as counterparty,
+                   p.narration,
+                   CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END::float8 as credit_amount,
+                   CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END::float8 as debit_amount
+            FROM proceedings p
+            WHERE (p.cr_from = $1 OR p.db_to = $1) AND p.pending
+            ORDER BY p.clears_on NULLS LAST, p.id
+            ",
+            &[&ledger_id],
+        )?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let today = dates::today();
+
+        outln!("\nScheduled / Uncleared:");
+        outln!(
+            "{:<6} {:<15} {:<10} {:<30} {:<15} {:<15}",
+            "Id",
+            "Clears On",
+            "Counterparty",
+            "Narration",
+            "Credit",
+            "Debit"
+        );
+        outln!("{:-<90}", "");
+        let mut credits = 0.0;
+        let mut debits = 0.0;
+        for row in rows.iter() {
+            let id: i32 = row.get(0);
+            let clears_on: Option<NaiveDate> = row.get(1);
+            let counterparty: String = row.get(2);
+            let narration: String = row.get(3);
+            let credit_amount: f64 = row.get(4);
+            let debit_amount: f64 = row.get(5);
+            credits += credit_amount;
+            debits += debit_amount;
+
+            // Past its clearing date but never confirmed
+            let when = match clears_on {
+                Some(day) if day <= today => format!("{:<15}", format!("{} due", day)).yellow(),
+                Some(day) => format!("{:<15}", day.to_string()).normal(),
+                None => format!("{:<15}", "-").normal(),
+            };
+            outln!(
+                "{:<6} {} {:<10} {:<30} {:<15.2} {:<15.2}",
+                id,
+                when,
+                counterparty,
+                narration,
+                credit_amount,
+                debit_amount
+            );
+        }
+        outln!("{:-<90}", "");
+        outln!("{:<60} {:<15.2} {:<15.2}", "Uncleared", credits, debits);
+        outln!(
+            "{:<60} {:<15.2}",
+            "Net Balance Once Cleared",
+            balance + debits - credits
+        );
+        Ok(())
+    }
+
+    // Confirms a pending item so it counts towards balances from `on`
+    pub(crate) fn clear_item(&mut self, id: i32, on: Option<NaiveDate>) -> Result<(), WalletError> {
+        let row = self
+            .client
+            .query_opt("SELECT pending FROM proceedings WHERE id = $1", &[&id])?
+            .ok_or(WalletError::TransactionNotFound(id))?;
+        let pending: bool = row.get(0);
+        if !pending {
+            outln!("Transaction #{} has already cleared", id);
+            return Ok(());
+        }
+
+        let cleared_on: NaiveDate = self
+            .client
+            .query_one(
+                "UPDATE proceedings
+                 SET pending = false, clears_on = COALESCE($2, CURRENT_DATE), updated_at = CURRENT_TIMESTAMP
+                 WHERE id = $1
+                 RETURNING clears_on",
+                &[&id, &on],
+            )?
+            .get(0);
+        outln!("Cleared transaction #{} on {}", id, cleared_on);
+        Ok(())
+    }
+}
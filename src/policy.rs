@@ -0,0 +1,165 @@
+use crate::{SpendEntry, WalletDB, WalletError};
+
+// What a ledger demands of every spend posted to or from it
+struct LedgerPolicy {
+    code: String,
+    require_payee: bool,
+    require_project: bool,
+    required_tag: Option<String>,
+    default_narration: Option<String>,
+}
+
+// A tag satisfies `required` when it is the tag itself or qualified by it,
+// so `client` is met by both `client` and `client:acme`
+fn has_tag(tags: &[String], required: &str) -> bool {
+    tags.iter().any(|tag| {
+        let (name, _) = tag.split_once(':').unwrap_or((tag, ""));
+        name.trim().eq_ignore_ascii_case(required)
+    })
+}
+
+fn is_blank(value: &Option<String>) -> bool {
+    value.as_deref().is_none_or(|v| v.trim().is_empty())
+}
+
+impl WalletDB {
+    fn ledger_policies(&mut self, ledger_ids: &[i32]) -> Result<Vec<LedgerPolicy>, WalletError> {
+        let rows = self.client.query(
+            "SELECT l.code, p.require_payee, p.require_project, p.required_tag, p.default_narration
+             FROM ledger_policies p
+             JOIN ledgers l ON l.id = p.ledger_id
+             WHERE p.ledger_id = ANY($1)
+             ORDER BY array_position($1, p.ledger_id)",
+            &[&ledger_ids],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| LedgerPolicy {
+                code: row.get(0),
+                require_payee: row.get(1),
+                require_project: row.get(2),
+                required_tag: row.get(3),
+                default_narration: row.get(4),
+            })
+            .collect())
+    }
+
+    // Fills in a missing narration from the ledgers' defaults and rejects the
+    // entry if either ledger requires a payee, project or tag it lacks.
+    // `ledger_ids` is [patron, outlay]; the outlay's default wins.
+    pub(crate) fn apply_policies(
+        &mut self,
+        ledger_ids: [i32; 2],
+        entry: &mut SpendEntry,
+    ) -> Result<(), WalletError> {
+        let policies = self.ledger_policies(&[ledger_ids[1], ledger_ids[0]])?;
+
+        if entry.narration.trim().is_empty() {
+            entry.narration = policies
+                .iter()
+                .find_map(|policy| policy.default_narration.clone())
+                .ok_or_else(|| {
+                    WalletError::PolicyViolation(format!(
+                        "a narration is required ({} -> {} has no default narration)",
+                        entry.patron, entry.outlay
+                    ))
+                })?;
+        }
+
+        for policy in policies.iter() {
+            if policy.require_payee && is_blank(&entry.payee) {
+                return Err(WalletError::PolicyViolation(format!(
+                    "{} requires a payee (--payee NAME)",
+                    policy.code
+                )));
+            }
+            if policy.require_project && is_blank(&entry.project) {
+                return Err(WalletError::PolicyViolation(format!(
+                    "{} requires a project (--project NAME)",
+                    policy.code
+                )));
+            }
+            if let Some(required) = &policy.required_tag {
+                if !has_tag(&entry.tags, required) {
+                    return Err(WalletError::PolicyViolation(format!(
+                        "{} requires a {} tag (--tag {}:NAME)",
+                        policy.code, required, required
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Replaces the policy of `code`, or prints it when nothing is given
+    pub(crate) fn set_ledger_policy(
+        &mut self,
+        code: &str,
+        require_payee: bool,
+        require_project: bool,
+        required_tag: Option<&str>,
+        default_narration: Option<&str>,
+    ) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+        if require_payee || require_project || required_tag.is_some() || default_narration.is_some()
+        {
+            self.client.execute(
+                "INSERT INTO ledger_policies
+                     (ledger_id, require_payee, require_project, required_tag, default_narration)
+                 VALUES ($1, $2, $3, $4, $5)
+                 ON CONFLICT (ledger_id) DO UPDATE SET
+                     require_payee = EXCLUDED.require_payee,
+                     require_project = EXCLUDED.require_project,
+                     required_tag = EXCLUDED.required_tag,
+                     default_narration = EXCLUDED.default_narration,
+                     updated_at = CURRENT_TIMESTAMP",
+                &[
+                    &ledger_id,
+                    &require_payee,
+                    &require_project,
+                    &required_tag,
+                    &default_narration,
+                ],
+            )?;
+        }
+
+        let Some(policy) = self.ledger_policies(&[ledger_id])?.pop() else {
+            outln!("{} has no policy", code);
+            return Ok(());
+        };
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+        outln!("\nPolicy for {}:", code);
+        outln!("{:-<40}", "");
+        outln!("{:<20} {}", "Payee required", yes_no(policy.require_payee));
+        outln!(
+            "{:<20} {}",
+            "Project required",
+            yes_no(policy.require_project)
+        );
+        outln!(
+            "{:<20} {}",
+            "Tag required",
+            policy.required_tag.as_deref().unwrap_or("-")
+        );
+        outln!(
+            "{:<20} {}",
+            "Default narration",
+            policy.default_narration.as_deref().unwrap_or("-")
+        );
+        Ok(())
+    }
+
+    pub(crate) fn clear_ledger_policy(&mut self, code: &str) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+        let removed = self.client.execute(
+            "DELETE FROM ledger_policies WHERE ledger_id = $1",
+            &[&ledger_id],
+        )?;
+        if removed == 0 {
+            outln!("{} has no policy", code);
+        } else {
+            outln!("Removed the policy for {}", code);
+        }
+        Ok(())
+    }
+}
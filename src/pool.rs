@@ -0,0 +1,142 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+
+use postgres::{Client, NoTls};
+
+use crate::WalletError;
+
+// A small blocking connection pool. Connections are opened lazily up to
+// `max_size` and handed back to the pool when the `PooledClient` is dropped.
+// Every connection works in one schema, the selected profile's, and in the
+// user's time zone when one is configured (the server's otherwise).
+#[derive(Clone)]
+pub struct Pool {
+    inner: Arc<PoolInner>,
+}
+
+struct PoolInner {
+    url: String,
+    max_size: usize,
+    schema: String,
+    timezone: Option<String>,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<Client>,
+    open: usize,
+}
+
+impl Pool {
+    pub fn new(url: &str, max_size: usize, schema: &str, timezone: Option<&str>) -> Self {
+        Pool {
+            inner: Arc::new(PoolInner {
+                url: url.to_string(),
+                max_size: max_size.max(1),
+                schema: schema.to_string(),
+                timezone: timezone.map(str::to_string),
+                state: Mutex::new(PoolState {
+                    idle: Vec::new(),
+                    open: 0,
+                }),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    // Checks out a connection, opening a new one if the pool is below its
+    // size limit and waiting for one to be returned otherwise.
+    pub fn get(&self) -> Result<PooledClient, WalletError> {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if let Some(client) = state.idle.pop() {
+                return Ok(self.wrap(client));
+            }
+            if state.open < self.inner.max_size {
+                state.open += 1;
+                drop(state);
+                return match self.connect() {
+                    Ok(client) => Ok(self.wrap(client)),
+                    Err(e) => {
+                        self.inner.state.lock().unwrap().open -= 1;
+                        self.inner.available.notify_one();
+                        Err(e.into())
+                    }
+                };
+            }
+            state = self.inner.available.wait(state).unwrap();
+        }
+    }
+
+    // Only the schema itself is searched, so a profile never falls through
+    // to another profile's tables
+    fn connect(&self) -> Result<Client, postgres::Error> {
+        let mut client = Client::connect(&self.inner.url, NoTls)?;
+        client.batch_execute(&format!("SET search_path TO \"{}\"", self.inner.schema))?;
+        if let Some(timezone) = &self.inner.timezone {
+            client.execute("SELECT set_config('TimeZone', $1, false)", &[timezone])?;
+        }
+        Ok(client)
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.inner.max_size
+    }
+
+    pub fn schema(&self) -> &str {
+        &self.inner.schema
+    }
+
+    // A pool over the same database working in another schema
+    pub fn with_schema(&self, schema: &str) -> Pool {
+        Pool::new(
+            &self.inner.url,
+            self.inner.max_size,
+            schema,
+            self.inner.timezone.as_deref(),
+        )
+    }
+
+    fn wrap(&self, client: Client) -> PooledClient {
+        PooledClient {
+            client: Some(client),
+            pool: self.clone(),
+        }
+    }
+}
+
+pub struct PooledClient {
+    client: Option<Client>,
+    pool: Pool,
+}
+
+impl Deref for PooledClient {
+    type Target = Client;
+
+    fn deref(&self) -> &Client {
+        self.client.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledClient {
+    fn deref_mut(&mut self) -> &mut Client {
+        self.client.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledClient {
+    fn drop(&mut self) {
+        let Some(client) = self.client.take() else {
+            return;
+        };
+        let mut state = self.pool.inner.state.lock().unwrap();
+        if client.is_closed() {
+            // Broken connections are discarded so the next checkout reconnects
+            state.open -= 1;
+        } else {
+            state.idle.push(client);
+        }
+        self.pool.inner.available.notify_one();
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{output, WalletDB, WalletError};
+
+// Profile kept in the public schema, where data lived before profiles
+pub const DEFAULT_PROFILE: &str = "default";
+
+const SCHEMA_PREFIX: &str = "profile_";
+
+// Schema holding a profile's tables. Names are limited to lower-case
+// letters, digits and underscores so the schema can be formatted into SQL.
+pub fn schema_for(profile: &str) -> Result<String, WalletError> {
+    if profile == DEFAULT_PROFILE {
+        return Ok("public".to_string());
+    }
+    let valid = !profile.is_empty()
+        && profile.len() <= 30
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !valid {
+        return Err(WalletError::Profile(format!(
+            "'{}' is not a valid profile name; use up to 30 lower-case letters, digits or underscores",
+            profile
+        )));
+    }
+    Ok(format!("{}{}", SCHEMA_PREFIX, profile))
+}
+
+fn profile_for(schema: &str) -> &str {
+    schema
+        .strip_prefix(SCHEMA_PREFIX)
+        .unwrap_or(DEFAULT_PROFILE)
+}
+
+impl WalletDB {
+    fn schema_exists(&mut self, schema: &str) -> Result<bool, WalletError> {
+        Ok(self
+            .client
+            .query_opt("SELECT 1 FROM pg_namespace WHERE nspname = $1", &[&schema])?
+            .is_some())
+    }
+
+    // Fails unless the selected profile has been created
+    pub(crate) fn ensure_profile(&mut self) -> Result<(), WalletError> {
+        let schema = self.pool.schema().to_string();
+        if !self.schema_exists(&schema)? {
+            return Err(WalletError::Profile(format!(
+                "no profile '{}'; create it with `profile create {}`",
+                profile_for(&schema),
+                profile_for(&schema)
+            )));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn list_profiles(&mut self) -> Result<(), WalletError> {
+        let rows = self.client.query(
+            "SELECT nspname::text FROM pg_namespace
+             WHERE nspname = 'public' OR starts_with(nspname, $1)
+             ORDER BY nspname != 'public', nspname",
+            &[&SCHEMA_PREFIX],
+        )?;
+        let current = self.pool.schema().to_string();
+        outln!("\nProfiles:");
+        outln!("{:-<30}", "");
+        for row in rows.iter() {
+            let schema: String = row.get(0);
+            let marker = if schema == current { "*" } else { " " };
+            outln!("{} {}", marker, profile_for(&schema));
+        }
+        Ok(())
+    }
+
+    // Creates the profile's schema and sets up its tables, as db-setup does
+    pub(crate) fn create_profile(&mut self, name: &str) -> Result<(), WalletError> {
+        let schema = schema_for(name)?;
+        if self.schema_exists(&schema)? {
+            return Err(WalletError::Profile(format!(
+                "profile '{}' already exists",
+                name
+            )));
+        }
+        self.client
+            .batch_execute(&format!("CREATE SCHEMA \"{}\"", schema))?;
+        WalletDB::from_pool(&self.pool.with_schema(&schema))?.setup_db()?;
+        outln!(
+            "\nCreated profile {}; select it with --profile {}",
+            name,
+            name
+        );
+        Ok(())
+    }
+
+    pub(crate) fn delete_profile(&mut self, name: &str) -> Result<(), WalletError> {
+        if name == DEFAULT_PROFILE {
+            return Err(WalletError::Profile(
+                "the default profile cannot be deleted".to_string(),
+            ));
+        }
+        let schema = schema_for(name)?;
+        if !self.schema_exists(&schema)? {
+            return Err(WalletError::Profile(format!("no profile '{}'", name)));
+        }
+        let confirmed = output::confirm(
+            &format!(
+                "Delete profile {} and all of its ledgers and transactions? This cannot be undone.",
+                name
+            ),
+            false,
+        )?;
+        if !confirmed {
+            outln!("Profile not deleted.");
+            return Ok(());
+        }
+        self.client
+            .batch_execute(&format!("DROP SCHEMA \"{}\" CASCADE", schema))?;
+        outln!("Deleted profile {}", name);
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+// Straight-line projections of when a balance reaches a target, given the
+// rate it has been growing at.
+
+use chrono::{Duration, NaiveDate};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Completion {
+    Reached,
+    On(NaiveDate),
+    // The balance is not growing, so the target is never reached
+    Never,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    pub daily_rate: f64,
+    pub completion: Completion,
+    // Daily rate needed from today to hit the target by the deadline
+    pub required_rate: Option<f64>,
+    pub on_track: bool,
+}
+
+// Average daily change over the last `days` days
+pub fn daily_rate(change: f64, days: i64) -> f64 {
+    change / days.max(1) as f64
+}
+
+pub fn project(
+    balance: f64,
+    target: f64,
+    daily_rate: f64,
+    today: NaiveDate,
+    deadline: Option<NaiveDate>,
+) -> Projection {
+    let remaining = target - balance;
+    let completion = if remaining <= 0.0 {
+        Completion::Reached
+    } else if daily_rate <= 0.0 {
+        Completion::Never
+    } else {
+        let days = (remaining / daily_rate).ceil() as i64;
+        today
+            .checked_add_signed(Duration::days(days))
+            .map_or(Completion::Never, Completion::On)
+    };
+    let required_rate = deadline
+        .filter(|_| remaining > 0.0)
+        .map(|deadline| remaining / ((deadline - today).num_days().max(1)) as f64);
+    let on_track = match (completion, deadline) {
+        (Completion::Reached, _) => true,
+        (Completion::Never, _) => false,
+        (Completion::On(day), Some(deadline)) => day <= deadline,
+        (Completion::On(_), None) => true,
+    };
+    Projection {
+        daily_rate,
+        completion,
+        required_rate,
+        on_track,
+    }
+}
@@ -0,0 +1,227 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use dialoguer::{theme::ColorfulTheme, MultiSelect, Select};
+
+use crate::{dates, output, SpendEntry, WalletDB, WalletError};
+
+// A proceeding on the ledger not yet matched against a statement
+struct Unreconciled {
+    id: i32,
+    created_at: NaiveDateTime,
+    counterparty: String,
+    narration: String,
+    // Signed effect on the ledger's balance (debits - credits)
+    amount: f64,
+}
+
+impl WalletDB {
+    // Balance (debits - credits) from the ledger's non-pending entries before
+    // `cutoff`, counting only reconciled ones when `reconciled_only`
+    fn balance_before(
+        &mut self,
+        ledger_id: i32,
+        cutoff: NaiveDateTime,
+        reconciled_only: bool,
+    ) -> Result<f64, WalletError> {
+        let row = self.client.query_one(
+            "SELECT COALESCE(SUM(CASE WHEN db_to = $1 THEN amount ELSE 0 END -
+                                 CASE WHEN cr_from = $1 THEN amount ELSE 0 END), 0)::float8
+             FROM proceedings
+             WHERE (cr_from = $1 OR db_to = $1) AND NOT pending AND created_at < $2::timestamp
+                 AND (NOT $3 OR cleared_at IS NOT NULL)",
+            &[&ledger_id, &cutoff, &reconciled_only],
+        )?;
+        Ok(row.get(0))
+    }
+
+    fn unreconciled(
+        &mut self,
+        ledger_id: i32,
+        cutoff: NaiveDateTime,
+    ) -> Result<Vec<Unreconciled>, WalletError> {
+        let rows = self.client.query(
+            "
+            SELECT p.id, p.created_at::timestamp,
+                   CASE
+                       WHEN p.cr_from = $1 THEN (SELECT code FROM ledgers WHERE id = p.db_to)
+                       ELSE (SELECT code FROM ledgers WHERE id = p.cr_from)
+                   END as counterparty,
+                   p.narration,
+                   (CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END -
+                    CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END)::float8 as amount
+            FROM proceedings p
+            WHERE (p.cr_from = $1 OR p.db_to = $1)
+                AND NOT p.pending
+                AND p.cleared_at IS NULL
+                AND p.created_at < $2::timestamp
+            ORDER BY p.created_at, p.id
+            ",
+            &[&ledger_id, &cutoff],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Unreconciled {
+                id: row.get(0),
+                created_at: row.get(1),
+                counterparty: row.get(2),
+                narration: row.get(3),
+                amount: row.get(4),
+            })
+            .collect())
+    }
+
+    // Compares the ledger's balance on `as_of` with the bank statement, marks
+    // the entries that appear on it as cleared and, once confirmed, records
+    // an adjustment against `adjust_with` for whatever difference remains.
+    // Without prompts every listed entry is taken as cleared.
+    pub(crate) fn reconcile(
+        &mut self,
+        code: &str,
+        statement_balance: f64,
+        as_of: NaiveDate,
+        adjust_with: Option<&str>,
+    ) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+        let cutoff = as_of.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let theme = ColorfulTheme::default();
+
+        let computed = self.balance_before(ledger_id, cutoff, false)?;
+        let reconciled = self.balance_before(ledger_id, cutoff, true)?;
+        let items = self.unreconciled(ledger_id, cutoff)?;
+
+        outln!("\nReconciling {} as of {}", code, as_of);
+        outln!("{:<30} {:<15.2}", "Statement balance", statement_balance);
+        outln!("{:<30} {:<15.2}", "Computed balance", computed);
+        outln!(
+            "{:<30} {:<15.2}",
+            "Difference",
+            statement_balance - computed
+        );
+        outln!("{:<30} {:<15.2}", "Already reconciled", reconciled);
+
+        if !items.is_empty() {
+            outln!("\nUnreconciled:");
+            outln!(
+                "{:<6} {:<12} {:<12} {:<30} {:<15}",
+                "Id",
+                "Date",
+                "Counterparty",
+                "Narration",
+                "Amount"
+            );
+            outln!("{:-<78}", "");
+            for item in items.iter() {
+                outln!(
+                    "{:<6} {:<12} {:<12} {:<30} {:<15.2}",
+                    item.id,
+                    item.created_at.format("%Y-%m-%d").to_string(),
+                    item.counterparty,
+                    item.narration,
+                    item.amount
+                );
+            }
+            outln!("{:-<78}", "");
+
+            let selected: Vec<usize> = if output::interactive() {
+                let labels: Vec<String> = items
+                    .iter()
+                    .map(|item| {
+                        format!(
+                            "#{:<5} {} {:<10} {:>12.2}  {}",
+                            item.id,
+                            item.created_at.format("%Y-%m-%d"),
+                            item.counterparty,
+                            item.amount,
+                            item.narration
+                        )
+                    })
+                    .collect();
+                MultiSelect::with_theme(&theme)
+                    .with_prompt("Entries on the statement (space toggles, enter confirms)")
+                    .items(&labels)
+                    .defaults(&vec![true; labels.len()])
+                    .interact()?
+            } else {
+                (0..items.len()).collect()
+            };
+            let ids: Vec<i32> = selected.iter().map(|&i| items[i].id).collect();
+            if !ids.is_empty() {
+                self.client.execute(
+                    "UPDATE proceedings
+                     SET cleared_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP
+                     WHERE id = ANY($1)",
+                    &[&ids],
+                )?;
+            }
+            outln!("Marked {} of {} entries as cleared", ids.len(), items.len());
+        }
+
+        let reconciled = self.balance_before(ledger_id, cutoff, true)?;
+        // In whole cents, so float noise in the balances is neither shown
+        // nor recorded
+        let residual = ((statement_balance - reconciled) * 100.0).round() / 100.0;
+        if residual == 0.0 {
+            outln!(
+                "{} is reconciled: {:.2} matches the statement",
+                code,
+                reconciled
+            );
+            return Ok(());
+        }
+        outln!("{:<30} {:<15.2}", "Cleared balance", reconciled);
+        outln!("{:<30} {:<15.2}", "Residual difference", residual);
+
+        let counterparty = match adjust_with {
+            Some(counterparty) => counterparty.to_string(),
+            None if output::interactive() => {
+                let codes: Vec<String> = self
+                    .ledger_codes()?
+                    .into_iter()
+                    .filter(|other| other != code)
+                    .collect();
+                if codes.is_empty() {
+                    outln!("No other ledger to record an adjustment against.");
+                    return Ok(());
+                }
+                let choice = Select::with_theme(&theme)
+                    .with_prompt("Adjust against")
+                    .items(&codes)
+                    .default(0)
+                    .interact()?;
+                codes[choice].clone()
+            }
+            None => {
+                outln!("No adjustment recorded; pass --adjust-with CODE to record one.");
+                return Ok(());
+            }
+        };
+        let confirmed = output::confirm(
+            &format!(
+                "Record an adjustment of {:.2} between {} and {}?",
+                residual, code, counterparty
+            ),
+            true,
+        )?;
+        if !confirmed {
+            outln!("No adjustment recorded.");
+            return Ok(());
+        }
+
+        // A positive residual means the statement shows more than the ledger,
+        // so the ledger is debited
+        let (patron, outlay) = if residual > 0.0 {
+            (counterparty, code.to_string())
+        } else {
+            (code.to_string(), counterparty)
+        };
+        self.record_spend(SpendEntry {
+            patron,
+            outlay,
+            amount: residual.abs(),
+            narration: format!("Reconciliation adjustment for statement of {}", as_of),
+            created_at: as_of.and_hms_opt(23, 59, 59),
+            cleared_at: Some(dates::now()),
+            ..Default::default()
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+use std::fs;
+
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+
+use crate::template::{self, Context};
+use crate::{dates, pdf, WalletDB, WalletError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum ReviewFormat {
+    Html,
+    Pdf,
+    Text,
+}
+
+// Largest expenses listed under "Biggest Purchases"
+const TOP_PURCHASES: i64 = 10;
+
+const TEXT_TEMPLATE: &str = "\
+Annual Review {{year}}
+Generated {{generated}}
+
+Summary                              {{year:>15}} {{prior_year:>15}} {{change_heading:>10}}
+------------------------------------------------------------------------------
+{{#summary}}
+{{label:<36}} {{now:>15}} {{before:>15}} {{change:>10}}
+{{/summary}}
+
+Category Trends vs {{prior_year}}
+Code                                 {{year:>15}} {{prior_year:>15}} {{change_heading:>10}}
+------------------------------------------------------------------------------
+{{#categories}}
+{{code:<36}} {{now:>15}} {{before:>15}} {{change:>10}}
+{{/categories}}
+{{^categories}}
+No spending
+{{/categories}}
+
+Biggest Purchases
+Date       Ledger              Amount Narration
+------------------------------------------------------------------------------
+{{#purchases}}
+{{date:<10}} {{code:<10}} {{amount:>15}} {{narration}}
+{{/purchases}}
+{{^purchases}}
+No purchases
+{{/purchases}}
+
+Savings Rate by Month
+Month              Income        Expenses   Savings Rate   Year to Date
+------------------------------------------------------------------------------
+{{#months}}
+{{month:<10}} {{income:>15}} {{expenses:>15}} {{rate:>14}} {{to_date:>14}}
+{{/months}}
+
+Goal Progress
+Goal            Ledger              Target   Start of Year     End of Year   Progress
+-------------------------------------------------------------------------------------
+{{#goals}}
+{{name:<15}} {{code:<10}} {{target:>15}} {{start:>15}} {{end:>15}} {{progress:>10}}
+{{/goals}}
+{{^goals}}
+No goals
+{{/goals}}
+";
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Annual Review {{year}}</title>
+<style>
+  body { font-family: Helvetica, Arial, sans-serif; color: #1c2230; margin: 2em auto; max-width: 52em; }
+  h1 { margin-bottom: 0; }
+  .generated { color: #8c96aa; margin-top: 0.2em; }
+  h2 { border-bottom: 2px solid #5ec8a0; padding-bottom: 0.2em; margin-top: 2em; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { padding: 0.3em 0.6em; text-align: left; border-bottom: 1px solid #e3e6ec; }
+  td.amount, th.amount { text-align: right; font-variant-numeric: tabular-nums; }
+  .empty { color: #8c96aa; }
+  @media print { h2 { break-after: avoid; } tr { break-inside: avoid; } }
+</style>
+</head>
+<body>
+<h1>Annual Review {{year}}</h1>
+<p class="generated">Generated {{generated}}</p>
+
+<h2>Summary</h2>
+<table>
+  <tr><th></th><th class="amount">{{year}}</th><th class="amount">{{prior_year}}</th><th class="amount">{{change_heading}}</th></tr>
+{{#summary}}
+  <tr><td>{{label}}</td><td class="amount">{{now}}</td><td class="amount">{{before}}</td><td class="amount">{{change}}</td></tr>
+{{/summary}}
+</table>
+
+<h2>Category Trends vs {{prior_year}}</h2>
+<table>
+  <tr><th>Code</th><th class="amount">{{year}}</th><th class="amount">{{prior_year}}</th><th class="amount">{{change_heading}}</th></tr>
+{{#categories}}
+  <tr><td>{{code}}</td><td class="amount">{{now}}</td><td class="amount">{{before}}</td><td class="amount">{{change}}</td></tr>
+{{/categories}}
+{{^categories}}
+  <tr><td colspan="4" class="empty">No spending</td></tr>
+{{/categories}}
+</table>
+
+<h2>Biggest Purchases</h2>
+<table>
+  <tr><th>Date</th><th>Ledger</th><th class="amount">Amount</th><th>Narration</th></tr>
+{{#purchases}}
+  <tr><td>{{date}}</td><td>{{code}}</td><td class="amount">{{amount}}</td><td>{{narration}}</td></tr>
+{{/purchases}}
+{{^purchases}}
+  <tr><td colspan="4" class="empty">No purchases</td></tr>
+{{/purchases}}
+</table>
+
+<h2>Savings Rate by Month</h2>
+<table>
+  <tr><th>Month</th><th class="amount">Income</th><th class="amount">Expenses</th><th class="amount">Savings Rate</th><th class="amount">Year to Date</th></tr>
+{{#months}}
+  <tr><td>{{month}}</td><td class="amount">{{income}}</td><td class="amount">{{expenses}}</td><td class="amount">{{rate}}</td><td class="amount">{{to_date}}</td></tr>
+{{/months}}
+</table>
+
+<h2>Goal Progress</h2>
+<table>
+  <tr><th>Goal</th><th>Ledger</th><th class="amount">Target</th><th class="amount">Start of Year</th><th class="amount">End of Year</th><th class="amount">Progress</th></tr>
+{{#goals}}
+  <tr><td>{{name}}</td><td>{{code}}</td><td class="amount">{{target}}</td><td class="amount">{{start}}</td><td class="amount">{{end}}</td><td class="amount">{{progress}}</td></tr>
+{{/goals}}
+{{^goals}}
+  <tr><td colspan="6" class="empty">No goals</td></tr>
+{{/goals}}
+</table>
+</body>
+</html>
+"#;
+
+fn year_range(year: i32) -> Result<(NaiveDateTime, NaiveDateTime), WalletError> {
+    let start = NaiveDate::from_ymd_opt(year, 1, 1);
+    let end = NaiveDate::from_ymd_opt(year + 1, 1, 1);
+    match (start, end) {
+        (Some(start), Some(end)) => Ok((
+            start.and_hms_opt(0, 0, 0).unwrap(),
+            end.and_hms_opt(0, 0, 0).unwrap(),
+        )),
+        _ => Err(WalletError::InvalidDate(format!(
+            "{} is not a valid year",
+            year
+        ))),
+    }
+}
+
+fn savings_rate(income: f64, expenses: f64) -> String {
+    if income > 0.0 {
+        format!("{:.1}%", (income - expenses) / income * 100.0)
+    } else {
+        "-".to_string()
+    }
+}
+
+// Year-on-year change; "new" when there was nothing to compare against
+fn change(now: f64, before: f64) -> String {
+    if before.abs() >= 0.005 {
+        format!("{:+.1}%", (now - before) / before.abs() * 100.0)
+    } else if now.abs() >= 0.005 {
+        "new".to_string()
+    } else {
+        "-".to_string()
+    }
+}
+
+fn comparison(label: &str, now: String, before: String, change: String) -> Context {
+    let mut item = Context::new();
+    item.set("label", label)
+        .set("now", now)
+        .set("before", before)
+        .set("change", change);
+    item
+}
+
+impl WalletDB {
+    // Compiles a year's totals against the year before, category trends,
+    // biggest purchases, monthly savings rates and goal progress into one
+    // document, written to `out` or stdout. PDFs set the text layout and
+    // default to review-<year>.pdf.
+    pub(crate) fn generate_review(
+        &mut self,
+        year: i32,
+        format: ReviewFormat,
+        out: Option<&str>,
+    ) -> Result<(), WalletError> {
+        let context = self.review_context(year)?;
+        let document = match format {
+            ReviewFormat::Html => {
+                template::render(HTML_TEMPLATE, &context, template::escape_html).into_bytes()
+            }
+            ReviewFormat::Pdf => {
+                pdf::from_text(&template::render(TEXT_TEMPLATE, &context, template::plain))
+            }
+            ReviewFormat::Text => {
+                template::render(TEXT_TEMPLATE, &context, template::plain).into_bytes()
+            }
+        };
+
+        let default_path = format!("review-{}.pdf", year);
+        let out = out.or((format == ReviewFormat::Pdf).then_some(default_path.as_str()));
+        match out {
+            Some(path) => {
+                fs::write(path, document)?;
+                outln!("Wrote annual review for {} to {}", year, path);
+            }
+            None => out!("{}", String::from_utf8_lossy(&document)),
+        }
+        Ok(())
+    }
+
+    // Income from INCOME ledgers and net spend into EXPENSE ledgers for each
+    // month of `year`, January first
+    fn monthly_flows(&mut self, year: i32) -> Result<[(f64, f64); 12], WalletError> {
+        let (start, end) = year_range(year)?;
+        let rows = self.client.query(
+            "
+            SELECT EXTRACT(MONTH FROM p.created_at)::int as month,
+                   SUM(CASE WHEN ledger_kind_at(p.cr_from, p.created_at) = 'INCOME'
+                       THEN p.amount ELSE 0 END)::float8 as income,
+                   SUM(CASE WHEN ledger_kind_at(p.db_to, p.created_at) = 'EXPENSE'
+                       THEN p.amount ELSE 0 END -
+                       CASE WHEN ledger_kind_at(p.cr_from, p.created_at) = 'EXPENSE'
+                       THEN p.amount ELSE 0 END)::float8 as expenses
+            FROM proceedings p
+            WHERE p.created_at >= $1::timestamp AND p.created_at < $2::timestamp AND NOT p.pending
+            GROUP BY month
+            ",
+            &[&start, &end],
+        )?;
+        let mut flows = [(0.0, 0.0); 12];
+        for row in rows.iter() {
+            let month: i32 = row.get(0);
+            flows[(month - 1) as usize] = (row.get(1), row.get(2));
+        }
+        Ok(flows)
+    }
+
+    fn transaction_count(&mut self, year: i32) -> Result<i64, WalletError> {
+        let (start, end) = year_range(year)?;
+        let row = self.client.query_one(
+            "SELECT COUNT(*) FROM proceedings
+             WHERE created_at >= $1::timestamp AND created_at < $2::timestamp AND NOT pending",
+            &[&start, &end],
+        )?;
+        Ok(row.get(0))
+    }
+
+    fn review_context(&mut self, year: i32) -> Result<Context, WalletError> {
+        let (start, end) = year_range(year)?;
+        let (prior_start, prior_end) = year_range(year - 1)?;
+
+        let flows = self.monthly_flows(year)?;
+        let prior_flows = self.monthly_flows(year - 1)?;
+        let total = |flows: &[(f64, f64); 12]| {
+            flows.iter().fold((0.0, 0.0), |(i, e), (income, expenses)| {
+                (i + income, e + expenses)
+            })
+        };
+        let (income, expenses) = total(&flows);
+        let (prior_income, prior_expenses) = total(&prior_flows);
+        let count = self.transaction_count(year)?;
+        let prior_count = self.transaction_count(year - 1)?;
+
+        let amount = |value: f64| format!("{:.2}", value);
+        let summary = vec![
+            comparison(
+                "Income",
+                amount(income),
+                amount(prior_income),
+                change(income, prior_income),
+            ),
+            comparison(
+                "Expenses",
+                amount(expenses),
+                amount(prior_expenses),
+                change(expenses, prior_expenses),
+            ),
+            comparison(
+                "Net",
+                amount(income - expenses),
+                amount(prior_income - prior_expenses),
+                change(income - expenses, prior_income - prior_expenses),
+            ),
+            comparison(
+                "Savings rate",
+                savings_rate(income, expenses),
+                savings_rate(prior_income, prior_expenses),
+                String::new(),
+            ),
+            comparison(
+                "Transactions",
+                count.to_string(),
+                prior_count.to_string(),
+                change(count as f64, prior_count as f64),
+            ),
+        ];
+
+        // This year's categories largest first, then ones only seen last year
+        let current = self.expense_totals(start, end)?;
+        let mut prior = self.expense_totals(prior_start, prior_end)?;
+        let mut trends: Vec<(String, f64, f64)> = current
+            .into_iter()
+            .map(|(code, now)| {
+                let before = prior
+                    .iter()
+                    .position(|(c, _)| *c == code)
+                    .map(|at| prior.remove(at).1)
+                    .unwrap_or(0.0);
+                (code, now, before)
+            })
+            .collect();
+        trends.extend(prior.into_iter().map(|(code, before)| (code, 0.0, before)));
+        let categories: Vec<Context> = trends
+            .into_iter()
+            .map(|(code, now, before)| {
+                let mut item = Context::new();
+                item.set("code", code)
+                    .set("now", amount(now))
+                    .set("before", amount(before))
+                    .set("change", change(now, before));
+                item
+            })
+            .collect();
+
+        let rows = self.client.query(
+            "
+            SELECT p.created_at::timestamp, l.code, p.amount::float8, p.narration
+            FROM proceedings p
+            JOIN ledgers l ON l.id = p.db_to
+            WHERE ledger_kind_at(l.id, p.created_at) = 'EXPENSE'
+                AND p.created_at >= $1::timestamp AND p.created_at < $2::timestamp
+                AND NOT p.pending
+            ORDER BY p.amount DESC, p.created_at
+            LIMIT $3
+            ",
+            &[&start, &end, &TOP_PURCHASES],
+        )?;
+        let purchases: Vec<Context> = rows
+            .iter()
+            .map(|row| {
+                let created_at: NaiveDateTime = row.get(0);
+                let mut item = Context::new();
+                item.set("date", created_at.format("%Y-%m-%d"))
+                    .set("code", row.get::<_, String>(1))
+                    .set("amount", amount(row.get(2)))
+                    .set("narration", row.get::<_, String>(3));
+                item
+            })
+            .collect();
+
+        // The trajectory stops at the current month of a year in progress
+        let today = dates::today();
+        let last_month = if year == today.year() {
+            today.month() as usize
+        } else {
+            12
+        };
+        let mut to_date = (0.0, 0.0);
+        let months: Vec<Context> = flows
+            .iter()
+            .take(last_month)
+            .enumerate()
+            .map(|(i, (income, expenses))| {
+                to_date = (to_date.0 + income, to_date.1 + expenses);
+                let month = NaiveDate::from_ymd_opt(year, i as u32 + 1, 1).unwrap();
+                let mut item = Context::new();
+                item.set("month", month.format("%B"))
+                    .set("income", amount(*income))
+                    .set("expenses", amount(*expenses))
+                    .set("rate", savings_rate(*income, *expenses))
+                    .set("to_date", savings_rate(to_date.0, to_date.1));
+                item
+            })
+            .collect();
+
+        let rows = self.client.query(
+            "
+            SELECT g.name, l.code, g.target::float8,
+                   COALESCE(SUM(CASE WHEN p.created_at < $1::timestamp THEN
+                       CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END -
+                       CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END
+                   ELSE 0 END), 0)::float8 as start_balance,
+                   COALESCE(SUM(
+                       CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END -
+                       CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END
+                   ), 0)::float8 as end_balance
+            FROM goals g
+            JOIN ledgers l ON l.id = g.ledger_id
+            LEFT JOIN proceedings p ON (p.cr_from = l.id OR p.db_to = l.id)
+                AND NOT p.pending
+                AND p.created_at < $2::timestamp
+            GROUP BY g.id, g.name, g.target, g.target_date, l.code
+            ORDER BY g.target_date NULLS LAST, g.name
+            ",
+            &[&start, &end],
+        )?;
+        let goals: Vec<Context> = rows
+            .iter()
+            .map(|row| {
+                let target: f64 = row.get(2);
+                let end_balance: f64 = row.get(4);
+                let mut item = Context::new();
+                item.set("name", row.get::<_, String>(0))
+                    .set("code", row.get::<_, String>(1))
+                    .set("target", amount(target))
+                    .set("start", amount(row.get(3)))
+                    .set("end", amount(end_balance))
+                    .set(
+                        "progress",
+                        format!("{:.1}%", (end_balance / target * 100.0).max(0.0)),
+                    );
+                item
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context
+            .set("year", year)
+            .set("prior_year", year - 1)
+            .set("change_heading", "Change")
+            .set("generated", dates::now().format("%Y-%m-%d %H:%M"))
+            .list("summary", summary)
+            .list("categories", categories)
+            .list("purchases", purchases)
+            .list("months", months)
+            .list("goals", goals);
+        Ok(context)
+    }
+}
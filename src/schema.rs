@@ -1,3 +1,79 @@
+diesel::table! {
+    envelopes (id) {
+        id -> Int4,
+        ledger_id -> Int4,
+        month -> Date,
+        assigned -> Float8,
+        carried_in -> Float8,
+        created_at -> Nullable<Timestamp>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    fx_rates (currency, rate_on) {
+        #[max_length = 3]
+        currency -> Bpchar,
+        rate_on -> Date,
+        rate -> Numeric,
+    }
+}
+
+diesel::table! {
+    goals (id) {
+        id -> Int4,
+        #[max_length = 30]
+        name -> Varchar,
+        target -> Numeric,
+        target_date -> Nullable<Date>,
+        ledger_id -> Int4,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    invoice_payments (invoice_id, proceeding_id) {
+        invoice_id -> Int4,
+        proceeding_id -> Int4,
+        amount -> Numeric,
+    }
+}
+
+diesel::table! {
+    invoices (id) {
+        id -> Int4,
+        ledger_id -> Int4,
+        amount -> Numeric,
+        issued_on -> Date,
+        due_on -> Date,
+        note -> Nullable<Text>,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    ledger_kinds (id) {
+        id -> Int4,
+        ledger_id -> Int4,
+        #[max_length = 20]
+        kind -> Varchar,
+        effective_from -> Date,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    ledger_policies (ledger_id) {
+        ledger_id -> Int4,
+        require_payee -> Bool,
+        require_project -> Bool,
+        #[max_length = 30]
+        required_tag -> Nullable<Varchar>,
+        default_narration -> Nullable<Text>,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     ledgers (id) {
         id -> Int4,
@@ -15,16 +91,101 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    links (id) {
+        id -> Int4,
+        from_id -> Int4,
+        to_id -> Int4,
+        #[max_length = 20]
+        kind -> Varchar,
+        created_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    period_locks (month) {
+        month -> Date,
+        locked_at -> Nullable<Timestamp>,
+    }
+}
+
 diesel::table! {
     proceedings (id) {
         id -> Int4,
         cr_from -> Int4,
         db_to -> Int4,
-        amount -> Float8,
+        amount -> Numeric,
+        #[max_length = 3]
+        currency -> Bpchar,
         narration -> Text,
+        uuid -> Uuid,
+        effective_date -> Date,
+        created_at -> Nullable<Timestamptz>,
+        updated_at -> Nullable<Timestamptz>,
+        pending -> Bool,
+        clears_on -> Nullable<Date>,
+        payee -> Nullable<Text>,
+        project -> Nullable<Text>,
+        tags -> Array<Nullable<Text>>,
+        tax_amount -> Nullable<Numeric>,
+        tax_rate -> Nullable<Numeric>,
+        cleared_at -> Nullable<Timestamptz>,
+    }
+}
+
+diesel::table! {
+    snapshot_items (snapshot_id, proceeding_id) {
+        snapshot_id -> Int4,
+        proceeding_id -> Int4,
+        amount -> Numeric,
+    }
+}
+
+diesel::table! {
+    snapshots (id) {
+        id -> Int4,
+        ledger_id -> Int4,
+        balance -> Numeric,
+        taken_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    templates (id) {
+        id -> Int4,
+        #[max_length = 30]
+        name -> Varchar,
+        patron_id -> Int4,
+        outlay_id -> Int4,
+        amount -> Numeric,
+        narration -> Nullable<Text>,
         created_at -> Nullable<Timestamp>,
-        updated_at -> Nullable<Timestamp>,
     }
 }
 
-diesel::allow_tables_to_appear_in_same_query!(ledgers, proceedings,);
+diesel::joinable!(envelopes -> ledgers (ledger_id));
+diesel::joinable!(goals -> ledgers (ledger_id));
+diesel::joinable!(invoice_payments -> invoices (invoice_id));
+diesel::joinable!(invoice_payments -> proceedings (proceeding_id));
+diesel::joinable!(invoices -> ledgers (ledger_id));
+diesel::joinable!(ledger_kinds -> ledgers (ledger_id));
+diesel::joinable!(ledger_policies -> ledgers (ledger_id));
+diesel::joinable!(snapshot_items -> snapshots (snapshot_id));
+diesel::joinable!(snapshots -> ledgers (ledger_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    envelopes,
+    fx_rates,
+    goals,
+    invoice_payments,
+    invoices,
+    ledger_kinds,
+    ledger_policies,
+    ledgers,
+    links,
+    period_locks,
+    proceedings,
+    snapshot_items,
+    snapshots,
+    templates,
+);
@@ -0,0 +1,394 @@
+// A small HTTP/JSON API over the wallet, for phone shortcuts and simple web
+// dashboards. Requests are served one at a time on the calling thread.
+//
+//   GET  /ledgers                          every ledger
+//   GET  /report?period=month              spending per ledger; also takes
+//        &date=..., &from=...&to=... and &group_by=day|week|month|ledger|kind
+//   POST /spends                           {"patron", "outlay", "amount",
+//        "narration", "date", "payee", "project", "tags"}; patron, outlay
+//        and amount are required
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+use clap::ValueEnum;
+
+use crate::dates;
+use crate::group_by::GroupBy;
+use crate::json::{self, Json};
+use crate::{ReportPeriod, SpendEntry, WalletDB, WalletError};
+
+// Largest request body accepted
+const MAX_BODY: usize = 64 * 1024;
+
+struct Request {
+    method: String,
+    path: String,
+    query: Vec<(String, String)>,
+    authorization: Option<String>,
+    body: String,
+}
+
+impl Request {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+struct Response {
+    status: u16,
+    body: Json,
+}
+
+impl Response {
+    fn ok(body: Json) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response {
+            status,
+            body: json::object(vec![("error", json::string(message))]),
+        }
+    }
+}
+
+// Client mistakes are 400s; only database and I/O failures are the server's
+impl From<WalletError> for Response {
+    fn from(e: WalletError) -> Self {
+        let status = match e {
+            WalletError::Database(_) | WalletError::Io(_) => 500,
+            WalletError::LedgerNotFound(_) => 404,
+            WalletError::PeriodLocked(_) => 409,
+            _ => 400,
+        };
+        Response::error(status, e.to_string())
+    }
+}
+
+impl From<postgres::Error> for Response {
+    fn from(e: postgres::Error) -> Self {
+        WalletError::from(e).into()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        _ => "Internal Server Error",
+    }
+}
+
+// Decodes %XX escapes and '+' in a query string component
+fn url_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+                match u8::from_str_radix(hex, 16) {
+                    Ok(byte) => {
+                        out.push(byte);
+                        i += 2;
+                    }
+                    Err(_) => out.push(b'%'),
+                }
+            }
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn read_request(stream: &mut TcpStream) -> Result<Request, Response> {
+    let mut reader = BufReader::new(stream);
+    let bad = |message: &str| Response::error(400, message);
+
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .map_err(|_| bad("unreadable request"))?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad("malformed request line"));
+    };
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (url_decode(key), url_decode(value))
+        })
+        .collect();
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        query,
+        authorization: None,
+        body: String::new(),
+    };
+
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        reader
+            .read_line(&mut line)
+            .map_err(|_| bad("unreadable headers"))?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        let Some((name, value)) = header.split_once(':') else {
+            return Err(bad("malformed header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().map_err(|_| bad("bad Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            request.authorization = Some(value.to_string());
+        }
+    }
+    if content_length > MAX_BODY {
+        return Err(Response::error(413, "request body too large"));
+    }
+    let mut body = vec![0; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| bad("incomplete body"))?;
+    request.body = String::from_utf8(body).map_err(|_| bad("body is not UTF-8"))?;
+    Ok(request)
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> std::io::Result<()> {
+    let body = if response.status == 204 {
+        String::new()
+    } else {
+        response.body.to_string()
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Headers: Authorization, Content-Type\r\n\
+         Access-Control-Allow-Methods: GET, POST, OPTIONS\r\n\
+         Connection: close\r\n\r\n{}",
+        response.status,
+        reason(response.status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+// Optional string field; present but not a string is an error
+fn text_field(body: &Json, name: &str) -> Result<Option<String>, Response> {
+    match body.get(name) {
+        None | Some(Json::Null) => Ok(None),
+        Some(Json::String(s)) => Ok(Some(s.clone())),
+        Some(_) => Err(Response::error(
+            400,
+            format!("\"{}\" must be a string", name),
+        )),
+    }
+}
+
+fn required_text(body: &Json, name: &str) -> Result<String, Response> {
+    text_field(body, name)?.ok_or_else(|| Response::error(400, format!("\"{}\" is required", name)))
+}
+
+fn spend_entry(body: &Json) -> Result<SpendEntry, Response> {
+    if !matches!(body, Json::Object(_)) {
+        return Err(Response::error(400, "body must be a JSON object"));
+    }
+    let amount = body
+        .get("amount")
+        .and_then(Json::as_f64)
+        .ok_or_else(|| Response::error(400, "\"amount\" must be a number"))?;
+    let created_at = text_field(body, "date")?
+        .map(|date| dates::parse_day(&date, dates::today()))
+        .transpose()?
+        .map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+    let tags = match body.get("tags") {
+        None | Some(Json::Null) => Vec::new(),
+        Some(tags) => tags
+            .as_array()
+            .and_then(|tags| {
+                tags.iter()
+                    .map(|tag| tag.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()
+            })
+            .ok_or_else(|| Response::error(400, "\"tags\" must be an array of strings"))?,
+    };
+    Ok(SpendEntry {
+        patron: required_text(body, "patron")?,
+        outlay: required_text(body, "outlay")?,
+        amount,
+        narration: text_field(body, "narration")?.unwrap_or_default(),
+        created_at,
+        payee: text_field(body, "payee")?,
+        project: text_field(body, "project")?,
+        tags,
+        ..Default::default()
+    })
+}
+
+fn report_period(request: &Request) -> Result<ReportPeriod, Response> {
+    let param = |name| request.param(name).map(str::to_string);
+    match (param("period"), param("date"), param("from"), param("to")) {
+        (Some(period), None, None, None) => ReportPeriod::from_str(&period, true)
+            .map_err(|_| Response::error(400, "period must be today, week, month or all")),
+        (None, Some(date), None, None) => Ok(ReportPeriod::Date(date)),
+        (None, None, Some(from), Some(to)) => Ok(ReportPeriod::FromTo { from, to }),
+        (None, None, None, None) => Ok(ReportPeriod::All),
+        _ => Err(Response::error(
+            400,
+            "give one of period, date, or from and to",
+        )),
+    }
+}
+
+impl WalletDB {
+    pub(crate) fn serve(&mut self, listen: &str, token: Option<&str>) -> Result<(), WalletError> {
+        let listener = TcpListener::bind(listen)?;
+        outln!(
+            "Serving the spendlog API on http://{}",
+            listener.local_addr()?
+        );
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("Failed to accept connection: {}", e);
+                    continue;
+                }
+            };
+            stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+            let response = match read_request(&mut stream) {
+                Ok(request) => {
+                    let response = self.handle(&request, token);
+                    outln!("{} {} {}", request.method, request.path, response.status);
+                    response
+                }
+                Err(response) => response,
+            };
+            if let Err(e) = write_response(&mut stream, &response) {
+                eprintln!("Failed to send response: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle(&mut self, request: &Request, token: Option<&str>) -> Response {
+        if request.method == "OPTIONS" {
+            return Response {
+                status: 204,
+                body: Json::Null,
+            };
+        }
+        if let Some(token) = token {
+            let expected = format!("Bearer {}", token);
+            if request.authorization.as_deref() != Some(expected.as_str()) {
+                return Response::error(401, "missing or wrong bearer token");
+            }
+        }
+        let result = match (request.method.as_str(), request.path.as_str()) {
+            ("GET", "/ledgers") => self.api_ledgers(),
+            ("GET", "/report") => self.api_report(request),
+            ("POST", "/spends") => self.api_add_spend(request),
+            (_, "/ledgers" | "/report" | "/spends") => {
+                Err(Response::error(405, "method not allowed"))
+            }
+            _ => Err(Response::error(404, "no such endpoint")),
+        };
+        result.unwrap_or_else(|response| response)
+    }
+
+    fn api_ledgers(&mut self) -> Result<Response, Response> {
+        let rows = self.client.query(
+            "SELECT code, name, sort, kind FROM ledgers ORDER BY code",
+            &[],
+        )?;
+        let ledgers = rows
+            .iter()
+            .map(|row| {
+                json::object(vec![
+                    ("code", Json::String(row.get(0))),
+                    ("name", Json::String(row.get(1))),
+                    ("sort", Json::String(row.get(2))),
+                    ("kind", Json::String(row.get(3))),
+                ])
+            })
+            .collect();
+        Ok(Response::ok(Json::Array(ledgers)))
+    }
+
+    fn api_report(&mut self, request: &Request) -> Result<Response, Response> {
+        let period = report_period(request)?;
+        let (start, end, label) = period.bounds()?;
+        let rows: Vec<(String, f64)> = match request.param("group_by") {
+            Some(group_by) => {
+                let group_by = GroupBy::from_str(group_by, true).map_err(|_| {
+                    Response::error(400, "group_by must be day, week, month, ledger or kind")
+                })?;
+                self.grouped_totals(start, end, group_by)?
+                    .into_iter()
+                    .map(|group| (group.key, group.amount))
+                    .collect()
+            }
+            None => self
+                .spending_totals(start, end)?
+                .into_iter()
+                .map(|(code, _, amount)| (code, amount))
+                .collect(),
+        };
+        let total = rows.iter().fold(0.0, |sum, (_, amount)| sum + amount);
+        let groups = rows
+            .into_iter()
+            .map(|(key, amount)| {
+                json::object(vec![
+                    ("key", Json::String(key)),
+                    ("amount", Json::Number(amount)),
+                ])
+            })
+            .collect();
+        Ok(Response::ok(json::object(vec![
+            ("period", json::string(label)),
+            ("groups", Json::Array(groups)),
+            ("total", Json::Number(total)),
+        ])))
+    }
+
+    fn api_add_spend(&mut self, request: &Request) -> Result<Response, Response> {
+        let body = json::parse(&request.body)
+            .map_err(|e| Response::error(400, format!("invalid JSON: {}", e)))?;
+        let entry = spend_entry(&body)?;
+        self.proceed_spend_batch(std::slice::from_ref(&entry))?;
+        Ok(Response {
+            status: 201,
+            body: json::object(vec![
+                ("patron", json::string(entry.patron)),
+                ("outlay", json::string(entry.outlay)),
+                ("amount", Json::Number(entry.amount)),
+            ]),
+        })
+    }
+}
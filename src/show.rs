@@ -0,0 +1,128 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use colored::Colorize;
+
+use crate::{WalletDB, WalletError};
+
+fn label(name: &str) -> colored::ColoredString {
+    format!("{:<12}", format!("{}:", name)).cyan().bold()
+}
+
+impl WalletDB {
+    // Prints every stored field of one proceeding. The v2 columns are read
+    // through to_jsonb so this also works on databases not yet upgraded.
+    pub(crate) fn show_proceeding(&mut self, id: i32) -> Result<(), WalletError> {
+        let row = self
+            .client
+            .query_opt(
+                "
+                SELECT f.code, f.name, ledger_kind_at(f.id, p.created_at),
+                       t.code, t.name, ledger_kind_at(t.id, p.created_at),
+                       p.amount::float8,
+                       to_jsonb(p) ->> 'currency',
+                       p.narration,
+                       to_jsonb(p) ->> 'effective_date',
+                       p.created_at::timestamp,
+                       p.updated_at::timestamp,
+                       to_jsonb(p) ->> 'uuid',
+                       p.pending,
+                       p.clears_on,
+                       p.payee,
+                       p.project,
+                       p.tags,
+                       p.tax_amount::float8,
+                       p.tax_rate::float8,
+                       p.cleared_at::timestamp
+                FROM proceedings p
+                JOIN ledgers f ON f.id = p.cr_from
+                JOIN ledgers t ON t.id = p.db_to
+                WHERE p.id = $1
+                ",
+                &[&id],
+            )?
+            .ok_or(WalletError::TransactionNotFound(id))?;
+
+        let ledger = |code: String, name: String, kind: String| {
+            format!("{} - {} ({})", code.bold(), name, kind)
+        };
+        let timestamp = |at: Option<NaiveDateTime>| {
+            at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_else(|| "-".to_string())
+        };
+        let amount: f64 = row.get(6);
+        let currency: Option<String> = row.get(7);
+        let effective: Option<String> = row.get(9);
+
+        outln!("\n{}", format!("Transaction #{}", id).bold());
+        outln!("{:-<50}", "");
+        outln!(
+            "{} {}",
+            label("From"),
+            ledger(row.get(0), row.get(1), row.get(2))
+        );
+        outln!(
+            "{} {}",
+            label("To"),
+            ledger(row.get(3), row.get(4), row.get(5))
+        );
+        outln!(
+            "{} {}",
+            label("Amount"),
+            match currency {
+                Some(currency) => format!("{:.2} {}", amount, currency),
+                None => format!("{:.2}", amount),
+            }
+            .bold()
+        );
+        outln!("{} {}", label("Narration"), row.get::<_, String>(8));
+        if let Some(payee) = row.get::<_, Option<String>>(15) {
+            outln!("{} {}", label("Payee"), payee);
+        }
+        if let Some(project) = row.get::<_, Option<String>>(16) {
+            outln!("{} {}", label("Project"), project);
+        }
+        let tags: Vec<String> = row.get(17);
+        if !tags.is_empty() {
+            outln!("{} {}", label("Tags"), tags.join(", "));
+        }
+        match (row.get::<_, Option<f64>>(18), row.get::<_, Option<f64>>(19)) {
+            (Some(tax), Some(rate)) => outln!("{} {:.2} ({}%)", label("Tax"), tax, rate),
+            (Some(tax), None) => outln!("{} {:.2}", label("Tax"), tax),
+            _ => {}
+        }
+        if let Some(effective) = effective {
+            outln!("{} {}", label("Effective"), effective);
+        }
+        let clears_on: Option<NaiveDate> = row.get(14);
+        match (row.get::<_, bool>(13), clears_on) {
+            (true, Some(day)) => outln!(
+                "{} {}",
+                label("Status"),
+                format!("pending, clears on {}", day).yellow()
+            ),
+            (true, None) => outln!("{} {}", label("Status"), "pending".yellow()),
+            (false, Some(day)) => outln!("{} cleared on {}", label("Status"), day),
+            (false, None) => outln!("{} cleared", label("Status")),
+        }
+        if let Some(cleared_at) = row.get::<_, Option<NaiveDateTime>>(20) {
+            outln!("{} {}", label("Reconciled"), timestamp(Some(cleared_at)));
+        }
+        let links = self.client.query(
+            "SELECT from_id, to_id, kind FROM links WHERE from_id = $1 OR to_id = $1 ORDER BY id",
+            &[&id],
+        )?;
+        for link in links.iter() {
+            let (from, to, kind): (i32, i32, String) = (link.get(0), link.get(1), link.get(2));
+            if from == id {
+                outln!("{} #{} ({})", label("Went on in"), to, kind);
+            } else {
+                outln!("{} #{} ({})", label("Came from"), from, kind);
+            }
+        }
+        outln!("{} {}", label("Created"), timestamp(row.get(10)));
+        outln!("{} {}", label("Updated"), timestamp(row.get(11)));
+        if let Some(uuid) = row.get::<_, Option<String>>(12) {
+            outln!("{} {}", label("UUID"), uuid.dimmed());
+        }
+        Ok(())
+    }
+}
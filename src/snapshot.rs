@@ -0,0 +1,180 @@
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::{WalletDB, WalletError};
+
+impl WalletDB {
+    // Records the ledger's current balance along with each proceeding's
+    // signed effect on it, so a later diff can name exactly what changed.
+    pub(crate) fn take_snapshot(&mut self, code: &str) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+
+        let mut transaction = self.client.transaction()?;
+        let snapshot_id: i32 = transaction
+            .query_one(
+                "INSERT INTO snapshots (ledger_id, balance) VALUES ($1, 0) RETURNING id",
+                &[&ledger_id],
+            )?
+            .get(0);
+        let items = transaction.execute(
+            "
+            INSERT INTO snapshot_items (snapshot_id, proceeding_id, amount)
+            SELECT $2, p.id,
+                   CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END -
+                   CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END
+            FROM proceedings p
+            WHERE (p.cr_from = $1 OR p.db_to = $1) AND NOT p.pending
+            ",
+            &[&ledger_id, &snapshot_id],
+        )?;
+        let row = transaction.query_one(
+            "
+            UPDATE snapshots
+            SET balance = (SELECT COALESCE(SUM(amount), 0) FROM snapshot_items WHERE snapshot_id = $1)
+            WHERE id = $1
+            RETURNING balance::float8, taken_at
+            ",
+            &[&snapshot_id],
+        )?;
+        transaction.commit()?;
+
+        let balance: f64 = row.get(0);
+        let taken_at: NaiveDateTime = row.get(1);
+        outln!(
+            "Snapshot #{} of {} taken {}: balance {:.2} across {} transactions",
+            snapshot_id,
+            code,
+            taken_at.format("%Y-%m-%d %H:%M:%S"),
+            balance,
+            items
+        );
+        Ok(())
+    }
+
+    // Compares the ledger now against its latest snapshot taken on or before
+    // `day`, listing every proceeding added, removed or changed since.
+    pub(crate) fn diff_snapshot(&mut self, code: &str, day: NaiveDate) -> Result<(), WalletError> {
+        let ledger_id = self.retrieve_ledger_id(code)?;
+        let cutoff = day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let snapshot = self
+            .client
+            .query_opt(
+                "SELECT id, balance::float8, taken_at FROM snapshots
+                 WHERE ledger_id = $1 AND taken_at < $2
+                 ORDER BY taken_at DESC, id DESC
+                 LIMIT 1",
+                &[&ledger_id, &cutoff],
+            )?
+            .ok_or_else(|| {
+                WalletError::NoSnapshot(format!("{} has no snapshot on or before {}", code, day))
+            })?;
+        let snapshot_id: i32 = snapshot.get(0);
+        let then_balance: f64 = snapshot.get(1);
+        let taken_at: NaiveDateTime = snapshot.get(2);
+
+        let rows = self.client.query(
+            "
+            WITH current AS (
+                SELECT p.id,
+                       CASE WHEN p.db_to = $1 THEN p.amount ELSE 0 END -
+                       CASE WHEN p.cr_from = $1 THEN p.amount ELSE 0 END AS amount
+                FROM proceedings p
+                WHERE (p.cr_from = $1 OR p.db_to = $1) AND NOT p.pending
+            ),
+            snapped AS (
+                SELECT proceeding_id AS id, amount FROM snapshot_items WHERE snapshot_id = $2
+            )
+            SELECT COALESCE(c.id, s.id) AS id,
+                   s.amount::float8,
+                   c.amount::float8,
+                   p.created_at::timestamp,
+                   (SELECT code FROM ledgers
+                    WHERE id = CASE WHEN p.cr_from = $1 THEN p.db_to ELSE p.cr_from END),
+                   p.narration
+            FROM current c
+            FULL OUTER JOIN snapped s ON s.id = c.id
+            LEFT JOIN proceedings p ON p.id = COALESCE(c.id, s.id)
+            WHERE s.amount IS DISTINCT FROM c.amount
+            ORDER BY p.created_at NULLS FIRST, id
+            ",
+            &[&ledger_id, &snapshot_id],
+        )?;
+        let now_balance: f64 = self
+            .client
+            .query_one(
+                "SELECT (COALESCE(SUM(CASE WHEN db_to = $1 THEN amount ELSE 0 END), 0) -
+                         COALESCE(SUM(CASE WHEN cr_from = $1 THEN amount ELSE 0 END), 0))::float8
+                 FROM proceedings WHERE (cr_from = $1 OR db_to = $1) AND NOT pending",
+                &[&ledger_id],
+            )?
+            .get(0);
+
+        outln!(
+            "\nSnapshot #{} of {} taken {}",
+            snapshot_id,
+            code,
+            taken_at.format("%Y-%m-%d %H:%M:%S")
+        );
+        outln!("{:<30} {:<15.2}", "Balance at snapshot", then_balance);
+        outln!("{:<30} {:<15.2}", "Balance now", now_balance);
+        outln!("{:<30} {:<15.2}", "Difference", now_balance - then_balance);
+
+        if rows.is_empty() {
+            outln!("\nNo transactions changed since the snapshot.");
+            return Ok(());
+        }
+        outln!(
+            "\n{:<8} {:<6} {:<12} {:<12} {:<30} {:<12} {:<12} {:<12}",
+            "Change",
+            "Id",
+            "Date",
+            "Counterparty",
+            "Narration",
+            "Then",
+            "Now",
+            "Effect"
+        );
+        outln!("{:-<110}", "");
+        let mut explained = 0.0;
+        for row in rows.iter() {
+            let id: i32 = row.get(0);
+            let then: Option<f64> = row.get(1);
+            let now: Option<f64> = row.get(2);
+            let created_at: Option<NaiveDateTime> = row.get(3);
+            let counterparty: Option<String> = row.get(4);
+            let narration: Option<String> = row.get(5);
+
+            let change = match (then, now) {
+                (None, Some(_)) => "Added",
+                (Some(_), None) => "Removed",
+                _ => "Changed",
+            };
+            let effect = now.unwrap_or(0.0) - then.unwrap_or(0.0);
+            explained += effect;
+            let amount = |value: Option<f64>| {
+                value
+                    .map(|v| format!("{:.2}", v))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+            outln!(
+                "{:<8} {:<6} {:<12} {:<12} {:<30} {:<12} {:<12} {:<12.2}",
+                change,
+                id,
+                created_at
+                    .map(|d| d.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+                counterparty.unwrap_or_else(|| "-".to_string()),
+                narration.unwrap_or_else(|| "(deleted)".to_string()),
+                amount(then),
+                amount(now),
+                effect
+            );
+        }
+        outln!("{:-<110}", "");
+        outln!(
+            "{:<98} {:<12.2}",
+            format!("{} transactions account for", rows.len()),
+            explained
+        );
+        Ok(())
+    }
+}
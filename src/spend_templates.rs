@@ -0,0 +1,216 @@
+use chrono::NaiveDateTime;
+use dialoguer::{theme::ColorfulTheme, Input, Select};
+
+use crate::{SpendEntry, WalletDB, WalletError};
+
+// A saved spend, recorded again with `t NAME` or picked in the interactive
+// spend prompt
+struct SpendTemplate {
+    name: String,
+    patron: String,
+    outlay: String,
+    amount: f64,
+    narration: Option<String>,
+}
+
+impl SpendTemplate {
+    fn describe(&self) -> String {
+        format!(
+            "{} -> {} {:.2} ({})",
+            self.patron,
+            self.outlay,
+            self.amount,
+            self.narration.as_deref().unwrap_or("default narration")
+        )
+    }
+
+    fn entry(&self) -> SpendEntry {
+        SpendEntry {
+            patron: self.patron.clone(),
+            outlay: self.outlay.clone(),
+            amount: self.amount,
+            narration: self.narration.clone().unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+}
+
+const TEMPLATES_QUERY: &str = "
+    SELECT t.name, f.code, o.code, t.amount::float8, t.narration
+    FROM templates t
+    JOIN ledgers f ON f.id = t.patron_id
+    JOIN ledgers o ON o.id = t.outlay_id
+";
+
+impl WalletDB {
+    fn spend_templates(&mut self, name: Option<&str>) -> Result<Vec<SpendTemplate>, WalletError> {
+        let rows = self.client.query(
+            &format!(
+                "{} WHERE $1::text IS NULL OR t.name = $1 ORDER BY t.name",
+                TEMPLATES_QUERY
+            ),
+            &[&name],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| SpendTemplate {
+                name: row.get(0),
+                patron: row.get(1),
+                outlay: row.get(2),
+                amount: row.get(3),
+                narration: row.get(4),
+            })
+            .collect())
+    }
+
+    pub(crate) fn add_spend_template(
+        &mut self,
+        name: &str,
+        patron: &str,
+        outlay: &str,
+        amount: f64,
+        narration: Option<&str>,
+    ) -> Result<(), WalletError> {
+        if amount <= 0.0 {
+            return Err(WalletError::InvalidAmount(
+                "Amount must be positive".to_string(),
+            ));
+        }
+        let patron_id = self.retrieve_ledger_id(patron)?;
+        let outlay_id = self.retrieve_ledger_id(outlay)?;
+        self.client.execute(
+            "INSERT INTO templates (name, patron_id, outlay_id, amount, narration)
+             VALUES ($1, $2, $3, $4::float8, $5)
+             ON CONFLICT (name) DO UPDATE SET
+                 patron_id = EXCLUDED.patron_id,
+                 outlay_id = EXCLUDED.outlay_id,
+                 amount = EXCLUDED.amount,
+                 narration = EXCLUDED.narration",
+            &[&name, &patron_id, &outlay_id, &amount, &narration],
+        )?;
+        let template = SpendTemplate {
+            name: name.to_string(),
+            patron: patron.to_string(),
+            outlay: outlay.to_string(),
+            amount,
+            narration: narration.map(str::to_string),
+        };
+        outln!("Saved template {}: {}", template.name, template.describe());
+        Ok(())
+    }
+
+    pub(crate) fn list_spend_templates(&mut self) -> Result<(), WalletError> {
+        let templates = self.spend_templates(None)?;
+        outln!("\nSpend Templates:");
+        outln!(
+            "{:<15} {:<10} {:<10} {:<12} {:<30}",
+            "Name",
+            "From",
+            "To",
+            "Amount",
+            "Narration"
+        );
+        outln!("{:-<80}", "");
+        for template in templates.iter() {
+            outln!(
+                "{:<15} {:<10} {:<10} {:<12.2} {:<30}",
+                template.name,
+                template.patron,
+                template.outlay,
+                template.amount,
+                template.narration.as_deref().unwrap_or("-")
+            );
+        }
+        if templates.is_empty() {
+            outln!("No templates yet. Add one with `template add NAME PATRON OUTLAY AMOUNT`.");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn remove_spend_template(&mut self, name: &str) -> Result<(), WalletError> {
+        let removed = self
+            .client
+            .execute("DELETE FROM templates WHERE name = $1", &[&name])?;
+        if removed == 0 {
+            return Err(WalletError::TemplateNotFound(name.to_string()));
+        }
+        outln!("Removed template {}", name);
+        Ok(())
+    }
+
+    // Records the template's spend, with the amount and date overridden
+    // when given
+    pub(crate) fn spend_from_template(
+        &mut self,
+        name: &str,
+        amount: Option<f64>,
+        created_at: Option<NaiveDateTime>,
+    ) -> Result<(), WalletError> {
+        let template = self
+            .spend_templates(Some(name))?
+            .pop()
+            .ok_or_else(|| WalletError::TemplateNotFound(name.to_string()))?;
+        let mut entry = template.entry();
+        entry.amount = amount.unwrap_or(template.amount);
+        entry.created_at = created_at;
+        self.record_spend(entry)
+    }
+
+    // `spend` with no arguments: fills in the ledgers, amount and narration
+    // of `entry` from a template picked as a preset, or one prompt at a time
+    pub(crate) fn prompt_spend(&mut self, entry: &mut SpendEntry) -> Result<(), WalletError> {
+        let theme = ColorfulTheme::default();
+        let templates = self.spend_templates(None)?;
+        let mut items: Vec<String> = templates
+            .iter()
+            .map(|template| format!("{:<15} {}", template.name, template.describe()))
+            .collect();
+        items.push("Enter a new spend".to_string());
+        let choice = Select::with_theme(&theme)
+            .with_prompt("Spend")
+            .items(&items)
+            .default(0)
+            .interact()?;
+
+        let mut amount = Input::<f64>::with_theme(&theme).with_prompt("Amount");
+        match templates.get(choice) {
+            Some(template) => {
+                entry.patron = template.patron.clone();
+                entry.outlay = template.outlay.clone();
+                entry.narration = template.narration.clone().unwrap_or_default();
+                amount = amount.default(template.amount);
+            }
+            None => {
+                let codes = self.ledger_codes()?;
+                let patron = Select::with_theme(&theme)
+                    .with_prompt("From ledger")
+                    .items(&codes)
+                    .default(0)
+                    .interact()?;
+                let outlay = Select::with_theme(&theme)
+                    .with_prompt("To ledger")
+                    .items(&codes)
+                    .default(0)
+                    .interact()?;
+                entry.patron = codes[patron].clone();
+                entry.outlay = codes[outlay].clone();
+            }
+        }
+
+        entry.amount = amount
+            .validate_with(|value: &f64| {
+                if *value > 0.0 {
+                    Ok(())
+                } else {
+                    Err("Amount must be positive")
+                }
+            })
+            .interact_text()?;
+        entry.narration = Input::with_theme(&theme)
+            .with_prompt("Narration")
+            .with_initial_text(entry.narration.clone())
+            .allow_empty(true)
+            .interact_text()?;
+        Ok(())
+    }
+}
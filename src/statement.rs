@@ -0,0 +1,239 @@
+use std::fs;
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+
+use crate::budget::{month_range, month_start};
+use crate::template::{self, Context};
+use crate::{dates, WalletDB, WalletError};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StatementFormat {
+    Html,
+    Text,
+}
+
+const TEXT_TEMPLATE: &str = "\
+Statement for {{month}}
+Generated {{generated}}
+
+Summary
+Income                         {{income:>15}}
+Expenses                       {{expenses:>15}}
+Net                            {{net:>15}}
+Savings rate                   {{savings_rate:>15}}
+Transactions                   {{count:>15}}
+
+Ledger Totals
+Code       Name                              Net Amount
+-------------------------------------------------------
+{{#ledgers}}
+{{code:<10}} {{name:<30}} {{amount:>13}}
+{{/ledgers}}
+{{^ledgers}}
+No activity
+{{/ledgers}}
+
+Daily Calendar
+Date            Day           Total Spent
+----------------------------------------
+{{#days}}
+{{date:<15}} {{weekday:<10}} {{amount:>13}}
+{{/days}}
+{{^days}}
+No spending
+{{/days}}
+----------------------------------------
+Total                       {{days_total:>13}}
+
+Transactions
+Id     Date                 From       To                  Amount Narration
+-------------------------------------------------------------------------------------------
+{{#transactions}}
+{{id:<6}} {{date:<20}} {{from:<10}} {{to:<10}} {{amount:>15}} {{narration}}
+{{/transactions}}
+{{^transactions}}
+No transactions
+{{/transactions}}
+";
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Statement for {{month}}</title>
+<style>
+  body { font-family: Helvetica, Arial, sans-serif; color: #1c2230; margin: 2em auto; max-width: 52em; }
+  h1 { margin-bottom: 0; }
+  .generated { color: #8c96aa; margin-top: 0.2em; }
+  h2 { border-bottom: 2px solid #5ec8a0; padding-bottom: 0.2em; margin-top: 2em; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { padding: 0.3em 0.6em; text-align: left; border-bottom: 1px solid #e3e6ec; }
+  td.amount, th.amount { text-align: right; font-variant-numeric: tabular-nums; }
+  tr.total td { font-weight: bold; border-top: 2px solid #1c2230; }
+  .summary td:first-child { width: 60%; }
+  .empty { color: #8c96aa; }
+</style>
+</head>
+<body>
+<h1>Statement for {{month}}</h1>
+<p class="generated">Generated {{generated}}</p>
+
+<h2>Summary</h2>
+<table class="summary">
+  <tr><td>Income</td><td class="amount">{{income}}</td></tr>
+  <tr><td>Expenses</td><td class="amount">{{expenses}}</td></tr>
+  <tr><td>Net</td><td class="amount">{{net}}</td></tr>
+  <tr><td>Savings rate</td><td class="amount">{{savings_rate}}</td></tr>
+  <tr><td>Transactions</td><td class="amount">{{count}}</td></tr>
+</table>
+
+<h2>Ledger Totals</h2>
+<table>
+  <tr><th>Code</th><th>Name</th><th class="amount">Net Amount</th></tr>
+{{#ledgers}}
+  <tr><td>{{code}}</td><td>{{name}}</td><td class="amount">{{amount}}</td></tr>
+{{/ledgers}}
+{{^ledgers}}
+  <tr><td colspan="3" class="empty">No activity</td></tr>
+{{/ledgers}}
+</table>
+
+<h2>Daily Calendar</h2>
+<table>
+  <tr><th>Date</th><th>Day</th><th class="amount">Total Spent</th></tr>
+{{#days}}
+  <tr><td>{{date}}</td><td>{{weekday}}</td><td class="amount">{{amount}}</td></tr>
+{{/days}}
+{{^days}}
+  <tr><td colspan="3" class="empty">No spending</td></tr>
+{{/days}}
+  <tr class="total"><td colspan="2">Total</td><td class="amount">{{days_total}}</td></tr>
+</table>
+
+<h2>Transactions</h2>
+<table>
+  <tr><th>Id</th><th>Date</th><th>From</th><th>To</th><th class="amount">Amount</th><th>Narration</th></tr>
+{{#transactions}}
+  <tr><td>{{id}}</td><td>{{date}}</td><td>{{from}}</td><td>{{to}}</td><td class="amount">{{amount}}</td><td>{{narration}}</td></tr>
+{{/transactions}}
+{{^transactions}}
+  <tr><td colspan="6" class="empty">No transactions</td></tr>
+{{/transactions}}
+</table>
+</body>
+</html>
+"#;
+
+impl WalletDB {
+    // Compiles a month's summary, ledger totals, daily calendar and full
+    // transaction list into one statement, written to `out` or stdout.
+    pub(crate) fn generate_statement(
+        &mut self,
+        month: Option<&str>,
+        format: StatementFormat,
+        out: Option<&str>,
+    ) -> Result<(), WalletError> {
+        let month = month_start(month)?;
+        let label = month.format("%B %Y").to_string();
+        let context = self.statement_context(month)?;
+        let document = match format {
+            StatementFormat::Html => {
+                template::render(HTML_TEMPLATE, &context, template::escape_html)
+            }
+            StatementFormat::Text => template::render(TEXT_TEMPLATE, &context, template::plain),
+        };
+
+        match out {
+            Some(path) => {
+                fs::write(path, document)?;
+                outln!("Wrote statement for {} to {}", label, path);
+            }
+            None => out!("{}", document),
+        }
+        Ok(())
+    }
+
+    fn statement_context(&mut self, month: NaiveDate) -> Result<Context, WalletError> {
+        let (start, end) = month_range(month);
+        let last_second = end - Duration::seconds(1);
+
+        let income = self.month_income(month)?;
+        let expenses: f64 = self
+            .expense_totals(start, end)?
+            .iter()
+            .fold(0.0, |total, (_, amount)| total + amount);
+
+        let ledgers: Vec<Context> = self
+            .spending_totals(start, Some(last_second))?
+            .into_iter()
+            .filter(|(_, _, amount)| amount.abs() >= 0.005)
+            .map(|(code, name, amount)| {
+                let mut item = Context::new();
+                item.set("code", code)
+                    .set("name", name)
+                    .set("amount", format!("{:.2}", amount));
+                item
+            })
+            .collect();
+
+        let daily = self.daily_totals(start, last_second)?;
+        let days_total = daily.iter().fold(0.0, |total, (_, amount)| total + amount);
+        let days: Vec<Context> = daily
+            .iter()
+            .map(|(day, amount)| {
+                let mut item = Context::new();
+                item.set("date", day.format("%Y-%m-%d"))
+                    .set("weekday", day.format("%A"))
+                    .set("amount", format!("{:.2}", amount));
+                item
+            })
+            .collect();
+
+        let rows = self.client.query(
+            "
+            SELECT p.id, p.created_at::timestamp, f.code, t.code, p.amount::float8, p.narration
+            FROM proceedings p
+            JOIN ledgers f ON f.id = p.cr_from
+            JOIN ledgers t ON t.id = p.db_to
+            WHERE p.created_at >= $1::timestamp AND p.created_at < $2::timestamp AND NOT p.pending
+            ORDER BY p.created_at, p.id
+            ",
+            &[&start, &end],
+        )?;
+        let transactions: Vec<Context> = rows
+            .iter()
+            .map(|row| {
+                let created_at: NaiveDateTime = row.get(1);
+                let mut item = Context::new();
+                item.set("id", row.get::<_, i32>(0))
+                    .set("date", created_at.format("%Y-%m-%d %H:%M:%S"))
+                    .set("from", row.get::<_, String>(2))
+                    .set("to", row.get::<_, String>(3))
+                    .set("amount", format!("{:.2}", row.get::<_, f64>(4)))
+                    .set("narration", row.get::<_, String>(5));
+                item
+            })
+            .collect();
+
+        let savings_rate = if income > 0.0 {
+            format!("{:.1}%", (income - expenses) / income * 100.0)
+        } else {
+            "-".to_string()
+        };
+        let mut context = Context::new();
+        context
+            .set("month", month.format("%B %Y"))
+            .set("generated", dates::now().format("%Y-%m-%d %H:%M"))
+            .set("income", format!("{:.2}", income))
+            .set("expenses", format!("{:.2}", expenses))
+            .set("net", format!("{:.2}", income - expenses))
+            .set("savings_rate", savings_rate)
+            .set("count", transactions.len())
+            .set("days_total", format!("{:.2}", days_total))
+            .list("ledgers", ledgers)
+            .list("days", days)
+            .list("transactions", transactions);
+        Ok(context)
+    }
+}
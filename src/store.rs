@@ -0,0 +1,524 @@
+// Storage behind the core commands (ledgers, spends, the spending report and
+// recent transactions). WalletDB keeps everything in PostgreSQL; MemoryStore
+// holds it in the process, for `demo` and for exercising the commands
+// without a database. Both render through the same functions below.
+
+use chrono::NaiveDateTime;
+use postgres::fallible_iterator::FallibleIterator;
+
+use crate::fx::FxColumn;
+use crate::paging::PageArgs;
+use crate::{dates, ReportPeriod, SpendEntry, WalletDB, WalletError};
+
+#[derive(Clone, Debug)]
+pub(crate) struct Ledger {
+    pub code: String,
+    pub name: String,
+    pub description: String,
+    pub sort: String,
+    pub kind: String,
+}
+
+// A recorded spend as listed by `last`
+#[derive(Clone, Debug)]
+pub(crate) struct Proceeding {
+    pub created_at: NaiveDateTime,
+    pub patron: String,
+    pub outlay: String,
+    pub amount: f64,
+    pub narration: String,
+}
+
+pub(crate) trait WalletStore {
+    fn insert_ledger(&mut self, ledger: &Ledger) -> Result<(), WalletError>;
+
+    // All ledgers by code
+    fn ledgers(&mut self) -> Result<Vec<Ledger>, WalletError>;
+
+    // Stores every entry or none of them
+    fn insert_spends(&mut self, entries: &[SpendEntry]) -> Result<usize, WalletError>;
+
+    // Net amount per ledger (code, name, amount) between `start` and `end`,
+    // largest first
+    fn totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+    ) -> Result<Vec<(String, String, f64)>, WalletError>;
+
+    // How many proceedings there are in all
+    fn proceeding_count(&mut self) -> Result<i64, WalletError>;
+
+    // Passes a page of proceedings, newest first, to `visit` until it
+    // returns false
+    fn recent(
+        &mut self,
+        limit: i64,
+        offset: i64,
+        visit: &mut dyn FnMut(Proceeding) -> bool,
+    ) -> Result<(), WalletError>;
+}
+
+impl WalletStore for WalletDB {
+    fn insert_ledger(&mut self, ledger: &Ledger) -> Result<(), WalletError> {
+        self.client.execute(
+            "INSERT INTO ledgers (code, name, description, sort, kind) VALUES ($1, $2, $3, $4, $5)",
+            &[
+                &ledger.code,
+                &ledger.name,
+                &ledger.description,
+                &ledger.sort,
+                &ledger.kind,
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn ledgers(&mut self) -> Result<Vec<Ledger>, WalletError> {
+        let rows = self.client.query(
+            "SELECT code, name, COALESCE(description, ''), sort, kind FROM ledgers ORDER BY code",
+            &[],
+        )?;
+        Ok(rows
+            .iter()
+            .map(|row| Ledger {
+                code: row.get(0),
+                name: row.get(1),
+                description: row.get(2),
+                sort: row.get(3),
+                kind: row.get(4),
+            })
+            .collect())
+    }
+
+    fn insert_spends(&mut self, entries: &[SpendEntry]) -> Result<usize, WalletError> {
+        self.proceed_spend_batch(entries)
+    }
+
+    fn totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+    ) -> Result<Vec<(String, String, f64)>, WalletError> {
+        self.spending_totals(start, end)
+    }
+
+    fn proceeding_count(&mut self) -> Result<i64, WalletError> {
+        Ok(self
+            .client
+            .query_one("SELECT COUNT(*) FROM proceedings", &[])?
+            .get(0))
+    }
+
+    // Streamed like the ledger report, so a long page is never held in full
+    fn recent(
+        &mut self,
+        limit: i64,
+        offset: i64,
+        visit: &mut dyn FnMut(Proceeding) -> bool,
+    ) -> Result<(), WalletError> {
+        let mut rows = self.client.query_raw(
+            "
+            SELECT p.created_at::timestamp,
+                   (SELECT code FROM ledgers WHERE id = p.cr_from),
+                   (SELECT code FROM ledgers WHERE id = p.db_to),
+                   p.amount::float8,
+                   p.narration
+            FROM proceedings p
+            ORDER BY p.created_at DESC
+            LIMIT $1 OFFSET $2
+            ",
+            [limit, offset],
+        )?;
+        while let Some(row) = rows.next()? {
+            let proceeding = Proceeding {
+                created_at: row.get(0),
+                patron: row.get(1),
+                outlay: row.get(2),
+                amount: row.get(3),
+                narration: row.get(4),
+            };
+            if !visit(proceeding) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Ledgers and spends kept in memory for the life of the process. Ledger
+// kinds have no history here, so a ledger's current kind applies throughout.
+#[derive(Default)]
+pub(crate) struct MemoryStore {
+    ledgers: Vec<Ledger>,
+    // Entries as stored, each with its created_at filled in
+    entries: Vec<SpendEntry>,
+}
+
+impl MemoryStore {
+    fn ledger(&self, code: &str) -> Result<&Ledger, WalletError> {
+        self.ledgers
+            .iter()
+            .find(|ledger| ledger.code == code)
+            .ok_or_else(|| WalletError::LedgerNotFound(code.to_string()))
+    }
+}
+
+impl WalletStore for MemoryStore {
+    fn insert_ledger(&mut self, ledger: &Ledger) -> Result<(), WalletError> {
+        if self.ledger(&ledger.code).is_ok() {
+            return Err(WalletError::LedgerExists(ledger.code.clone()));
+        }
+        self.ledgers.push(ledger.clone());
+        self.ledgers.sort_by(|a, b| a.code.cmp(&b.code));
+        Ok(())
+    }
+
+    fn ledgers(&mut self) -> Result<Vec<Ledger>, WalletError> {
+        Ok(self.ledgers.clone())
+    }
+
+    fn insert_spends(&mut self, entries: &[SpendEntry]) -> Result<usize, WalletError> {
+        for entry in entries {
+            entry.validate()?;
+            self.ledger(&entry.patron)?;
+            self.ledger(&entry.outlay)?;
+        }
+        let now = dates::now();
+        self.entries.extend(entries.iter().map(|entry| SpendEntry {
+            created_at: entry.created_at.or(Some(now)),
+            ..entry.clone()
+        }));
+        Ok(entries.len())
+    }
+
+    fn totals(
+        &mut self,
+        start: NaiveDateTime,
+        end: Option<NaiveDateTime>,
+    ) -> Result<Vec<(String, String, f64)>, WalletError> {
+        // Each entry debits its outlay and, while the patron is a LIABILITY,
+        // reduces the patron's balance, as in spending_totals
+        let in_period = |entry: &&SpendEntry| {
+            let at = entry.created_at.unwrap_or_default();
+            !entry.pending && at >= start && end.is_none_or(|end| at <= end)
+        };
+        let mut totals: Vec<(String, String, f64)> = self
+            .ledgers
+            .iter()
+            .map(|ledger| {
+                let amount = self
+                    .entries
+                    .iter()
+                    .filter(in_period)
+                    .map(|entry| {
+                        let mut amount = 0.0;
+                        if entry.outlay == ledger.code {
+                            amount += entry.amount;
+                        }
+                        if entry.patron == ledger.code && ledger.kind == "LIABILITY" {
+                            amount -= entry.amount;
+                        }
+                        amount
+                    })
+                    .sum();
+                (ledger.code.clone(), ledger.name.clone(), amount)
+            })
+            .collect();
+        totals.sort_by(|a, b| b.2.total_cmp(&a.2));
+        Ok(totals)
+    }
+
+    fn proceeding_count(&mut self) -> Result<i64, WalletError> {
+        Ok(self.entries.len() as i64)
+    }
+
+    fn recent(
+        &mut self,
+        limit: i64,
+        offset: i64,
+        visit: &mut dyn FnMut(Proceeding) -> bool,
+    ) -> Result<(), WalletError> {
+        let mut entries: Vec<&SpendEntry> = self.entries.iter().collect();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.created_at));
+        for entry in entries.iter().skip(offset as usize).take(limit as usize) {
+            let proceeding = Proceeding {
+                created_at: entry.created_at.unwrap_or_default(),
+                patron: entry.patron.clone(),
+                outlay: entry.outlay.clone(),
+                amount: entry.amount,
+                narration: entry.narration.clone(),
+            };
+            if !visit(proceeding) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn list_ledgers(store: &mut dyn WalletStore) -> Result<(), WalletError> {
+    let ledgers = store.ledgers()?;
+
+    outln!("\nList of Ledgers:");
+    outln!(
+        "{:<10} {:<30} {:<10} {:<10}",
+        "Code",
+        "Name",
+        "Sort",
+        "Kind"
+    );
+    outln!("{:-<60}", "");
+    for ledger in ledgers {
+        outln!(
+            "{:<10} {:<30} {:<10} {:<10}",
+            ledger.code,
+            ledger.name,
+            ledger.sort,
+            ledger.kind
+        );
+    }
+    Ok(())
+}
+
+// The per-ledger spending report with its --also-in column, returning the
+// grand total. The caller adds any footer and the fx footnote.
+pub(crate) fn spending_report(
+    store: &mut dyn WalletStore,
+    period: &ReportPeriod,
+    fx: &FxColumn,
+) -> Result<f64, WalletError> {
+    let (start, end, period_str) = period.bounds()?;
+    let totals = store.totals(start, end)?;
+
+    outln!("\nSpending Report ({}):", period_str);
+    outln!(
+        "{:<10} {:<30} {:<15}{}",
+        "Code",
+        "Name",
+        "Net Amount",
+        fx.header()
+    );
+    outln!("{}", fx.rule(55));
+    let mut grand_total: f64 = 0.0;
+    for (code, name, net_amount) in totals.iter() {
+        grand_total += net_amount;
+        outln!(
+            "{:<10} {:<30} {:<15.2}{}",
+            code,
+            name,
+            net_amount,
+            fx.cell(*net_amount)
+        );
+    }
+    outln!("{}", fx.rule(55));
+    outln!(
+        "{:<40} {:<15.2}{}",
+        "Grand Total",
+        grand_total,
+        fx.cell(grand_total)
+    );
+    Ok(grand_total)
+}
+
+pub(crate) fn recent_report(
+    store: &mut dyn WalletStore,
+    paging: &PageArgs,
+) -> Result<(), WalletError> {
+    let limit = paging.limit.unwrap_or(10);
+    let total_rows = store.proceeding_count()?;
+    let page = (total_rows - paging.offset).clamp(0, limit);
+
+    outln!(
+        "\nRecent Transactions Report ({}):",
+        paging.describe(page as usize, total_rows)
+    );
+    outln!(
+        "{:<20} {:<10} {:<10} {:<15} {:<30}",
+        "Date",
+        "From",
+        "To",
+        "Amount",
+        "Narration"
+    );
+    outln!("{:-<85}", "");
+    let mut pager = paging.pager();
+    store.recent(limit, paging.offset, &mut |row| {
+        let line = format!(
+            "{:<20} {:<10} {:<10} {:<15.2} {:<30}",
+            row.created_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            row.patron,
+            row.outlay,
+            row.amount,
+            row.narration
+        );
+        pager.line(&line)
+    })?;
+    outln!("{:-<85}", "");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn store() -> MemoryStore {
+        let mut store = MemoryStore::default();
+        for (code, kind) in [
+            ("CASH", "ASSET"),
+            ("CARD", "LIABILITY"),
+            ("FOOD", "EXPENSE"),
+        ] {
+            store
+                .insert_ledger(&Ledger {
+                    code: code.to_string(),
+                    name: code.to_lowercase(),
+                    description: String::new(),
+                    sort: "DR".to_string(),
+                    kind: kind.to_string(),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn at(day: u32, hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 4, day)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn spend(patron: &str, outlay: &str, amount: f64, created_at: NaiveDateTime) -> SpendEntry {
+        SpendEntry {
+            patron: patron.to_string(),
+            outlay: outlay.to_string(),
+            amount,
+            narration: format!("{} -> {}", patron, outlay),
+            created_at: Some(created_at),
+            ..Default::default()
+        }
+    }
+
+    fn page(store: &mut MemoryStore, limit: i64, offset: i64) -> Vec<f64> {
+        let mut amounts = Vec::new();
+        store
+            .recent(limit, offset, &mut |row| {
+                amounts.push(row.amount);
+                true
+            })
+            .unwrap();
+        amounts
+    }
+
+    #[test]
+    fn ledgers_are_unique_and_sorted() {
+        let mut store = store();
+        let codes: Vec<String> = store
+            .ledgers()
+            .unwrap()
+            .into_iter()
+            .map(|l| l.code)
+            .collect();
+        assert_eq!(codes, ["CARD", "CASH", "FOOD"]);
+
+        let again = store.ledgers().unwrap()[0].clone();
+        assert!(matches!(
+            store.insert_ledger(&again),
+            Err(WalletError::LedgerExists(code)) if code == "CARD"
+        ));
+    }
+
+    #[test]
+    fn spends_are_stored_all_or_nothing() {
+        let mut store = store();
+        let entries = [
+            spend("CASH", "FOOD", 10.0, at(1, 9)),
+            spend("CASH", "NOPE", 20.0, at(1, 10)),
+        ];
+        assert!(matches!(
+            store.insert_spends(&entries),
+            Err(WalletError::LedgerNotFound(code)) if code == "NOPE"
+        ));
+        assert_eq!(store.proceeding_count().unwrap(), 0);
+
+        let invalid = spend("CASH", "FOOD", -5.0, at(1, 9));
+        assert!(store.insert_spends(&[invalid]).is_err());
+        assert_eq!(store.proceeding_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn totals_follow_the_spending_report_rules() {
+        let mut store = store();
+        let mut pending = spend("CASH", "FOOD", 1000.0, at(2, 9));
+        pending.pending = true;
+        store
+            .insert_spends(&[
+                spend("CASH", "FOOD", 100.0, at(1, 9)),
+                spend("CARD", "FOOD", 50.0, at(2, 12)),
+                spend("CASH", "CARD", 30.0, at(3, 18)),
+                spend("CASH", "FOOD", 500.0, at(20, 9)),
+                pending,
+            ])
+            .unwrap();
+
+        let totals = store.totals(at(1, 0), Some(at(10, 0))).unwrap();
+        let totals: Vec<(&str, f64)> = totals
+            .iter()
+            .map(|(code, _, amount)| (code.as_str(), *amount))
+            .collect();
+        // FOOD is debited 150; CARD is paid 30 down and charged 50; spending
+        // out of CASH does not count against it
+        assert_eq!(totals, [("FOOD", 150.0), ("CASH", 0.0), ("CARD", -20.0)]);
+
+        let open = store.totals(at(1, 0), None).unwrap();
+        assert_eq!(open[0].0, "FOOD");
+        assert_eq!(open[0].2, 650.0);
+    }
+
+    #[test]
+    fn recent_pages_newest_first() {
+        let mut store = store();
+        store
+            .insert_spends(&[
+                spend("CASH", "FOOD", 1.0, at(1, 9)),
+                spend("CASH", "FOOD", 3.0, at(3, 9)),
+                spend("CASH", "FOOD", 2.0, at(2, 9)),
+            ])
+            .unwrap();
+
+        assert_eq!(store.proceeding_count().unwrap(), 3);
+        assert_eq!(page(&mut store, 10, 0), [3.0, 2.0, 1.0]);
+        assert_eq!(page(&mut store, 1, 1), [2.0]);
+        assert!(page(&mut store, 10, 3).is_empty());
+
+        let mut seen = 0;
+        store
+            .recent(10, 0, &mut |_| {
+                seen += 1;
+                false
+            })
+            .unwrap();
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn undated_spends_are_dated_now() {
+        let mut store = store();
+        let mut entry = spend("CASH", "FOOD", 5.0, at(1, 9));
+        entry.created_at = None;
+        let before = dates::now();
+        store.insert_spends(&[entry]).unwrap();
+
+        let mut dated = None;
+        store
+            .recent(1, 0, &mut |row| {
+                dated = Some(row.created_at);
+                true
+            })
+            .unwrap();
+        assert!(dated.unwrap() >= before);
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::NaiveDate;
+
+use crate::{dates, WalletDB, WalletError};
+
+// Matching proceedings per week when grouped, or in one row otherwise.
+// With a ledger ($1) each amount counts as its net effect on that ledger
+// (debits - credits), as in the ledger report; without one, as is.
+const SUM_QUERY: &str = "
+    SELECT CASE WHEN $5 THEN date_trunc('week', p.created_at)::date END AS week,
+           COUNT(*),
+           COALESCE(SUM(CASE
+               WHEN $1::int IS NULL THEN p.amount
+               WHEN p.db_to = $1 AND p.cr_from = $1 THEN 0
+               WHEN p.db_to = $1 THEN p.amount
+               ELSE -p.amount
+           END), 0)::float8
+    FROM proceedings p
+    WHERE NOT p.pending
+        AND ($1::int IS NULL OR p.cr_from = $1 OR p.db_to = $1)
+        AND ($2::timestamp IS NULL OR p.created_at >= $2::timestamp)
+        AND ($3::timestamp IS NULL OR p.created_at < $3::timestamp)
+        AND ($4::text IS NULL OR p.narration ILIKE $4)
+    GROUP BY 1
+    ORDER BY 1
+";
+
+// Narrations containing `text`, case-insensitively. Text that already has
+// a LIKE wildcard is used as the whole pattern.
+fn like_pattern(text: &str) -> String {
+    if text.contains('%') || text.contains('_') {
+        text.to_string()
+    } else {
+        format!("%{}%", text)
+    }
+}
+
+impl WalletDB {
+    // Count and total of the proceedings matching the filters, for quick
+    // questions that do not need a full report
+    pub(crate) fn quick_sum(
+        &mut self,
+        ledger: Option<&str>,
+        from: Option<&str>,
+        to: Option<&str>,
+        narration_like: Option<&str>,
+        by_week: bool,
+    ) -> Result<(), WalletError> {
+        let ledger_id = ledger
+            .map(|code| self.retrieve_ledger_id(code))
+            .transpose()?;
+        let today = dates::today();
+        let from = from
+            .map(|from| dates::parse_span(from, today))
+            .transpose()?
+            .map(|(start, _)| start);
+        let to = to
+            .map(|to| dates::parse_span(to, today))
+            .transpose()?
+            .map(|(_, end)| end);
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(WalletError::DateRangeError(
+                    "The 'from' date must be earlier than or equal to the 'to' date.".to_string(),
+                ));
+            }
+        }
+        let start = from.map(|day| day.and_hms_opt(0, 0, 0).unwrap());
+        let end = to.map(|day| day.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap());
+        let pattern = narration_like.map(like_pattern);
+
+        let rows = self
+            .client
+            .query(SUM_QUERY, &[&ledger_id, &start, &end, &pattern, &by_week])?;
+        let rows: Vec<(Option<NaiveDate>, i64, f64)> = rows
+            .iter()
+            .map(|row| (row.get(0), row.get(1), row.get(2)))
+            .collect();
+        let count: i64 = rows.iter().map(|row| row.1).sum();
+        let total = rows.iter().fold(0.0, |total, row| total + row.2);
+
+        if by_week {
+            outln!("{:<12} {:>8} {:>15}", "Week Of", "Count", "Total");
+            outln!("{:-<37}", "");
+            for (week, count, total) in rows.iter() {
+                let week = week.map(|week| week.to_string()).unwrap_or_default();
+                outln!("{:<12} {:>8} {:>15.2}", week, count, total);
+            }
+            outln!("{:-<37}", "");
+            outln!("{:<12} {:>8} {:>15.2}", "Total", count, total);
+        } else {
+            outln!("{} transactions, total {:.2}", count, total);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+use chrono::Datelike;
+
+use crate::fx::FxArgs;
+use crate::interest::year_range;
+use crate::{dates, WalletDB, WalletError};
+
+// A tax rate given as "18%" or "18"
+pub fn parse_rate(input: &str) -> Result<f64, WalletError> {
+    let digits = input.trim().trim_end_matches('%').trim();
+    match digits.parse::<f64>() {
+        Ok(rate) if (0.0..100.0).contains(&rate) => Ok(rate),
+        _ => Err(WalletError::InvalidAmount(format!(
+            "Invalid tax rate '{}'; use a percentage such as 18%",
+            input
+        ))),
+    }
+}
+
+// Tax contained in a tax-inclusive `amount` at `rate` percent, to the cent
+pub fn included_tax(amount: f64, rate: f64) -> f64 {
+    (amount * rate / (100.0 + rate) * 100.0).round() / 100.0
+}
+
+impl WalletDB {
+    // Tax paid per ledger for each quarter of a year, the figures needed to
+    // claim input credit on business purchases
+    pub(crate) fn generate_tax_report(
+        &mut self,
+        year: Option<i32>,
+        fx_args: &FxArgs,
+    ) -> Result<(), WalletError> {
+        let year = year.unwrap_or_else(|| dates::today().year());
+        let (start, end) = year_range(year)?;
+        let fx = self.fx_column(fx_args, start.date(), end.date().pred_opt())?;
+
+        let rows = self.client.query(
+            "
+            SELECT l.code, l.name,
+                   EXTRACT(QUARTER FROM p.created_at)::int as quarter,
+                   SUM(p.tax_amount)::float8 as tax
+            FROM proceedings p
+            JOIN ledgers l ON l.id = p.db_to
+            WHERE p.created_at >= $1::timestamp AND p.created_at < $2::timestamp AND NOT p.pending
+                AND p.tax_amount > 0
+            GROUP BY l.code, l.name, quarter
+            ORDER BY l.code, quarter
+            ",
+            &[&start, &end],
+        )?;
+
+        let mut ledgers: BTreeMap<String, (String, [f64; 4])> = BTreeMap::new();
+        for row in rows.iter() {
+            let quarter: i32 = row.get(2);
+            ledgers
+                .entry(row.get(0))
+                .or_insert_with(|| (row.get(1), [0.0; 4]))
+                .1[(quarter - 1) as usize] += row.get::<_, f64>(3);
+        }
+
+        outln!("\nTax Paid Report ({}):", year);
+        outln!(
+            "{:<10} {:<30} {:<12} {:<12} {:<12} {:<12} {:<12}{}",
+            "Code",
+            "Name",
+            "Q1",
+            "Q2",
+            "Q3",
+            "Q4",
+            "Total",
+            fx.header()
+        );
+        outln!("{}", fx.rule(105));
+        let mut totals = [0.0; 4];
+        for (code, (name, quarters)) in ledgers.iter() {
+            for (total, tax) in totals.iter_mut().zip(quarters) {
+                *total += tax;
+            }
+            let total = quarters.iter().sum::<f64>();
+            outln!(
+                "{:<10} {:<30} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2}{}",
+                code,
+                name,
+                quarters[0],
+                quarters[1],
+                quarters[2],
+                quarters[3],
+                total,
+                fx.cell(total)
+            );
+        }
+        outln!("{}", fx.rule(105));
+        let total = totals.iter().fold(0.0, |sum, tax| sum + tax);
+        outln!(
+            "{:<41} {:<12.2} {:<12.2} {:<12.2} {:<12.2} {:<12.2}{}",
+            "Total Tax Paid",
+            totals[0],
+            totals[1],
+            totals[2],
+            totals[3],
+            total,
+            fx.cell(total)
+        );
+        fx.footnote();
+        Ok(())
+    }
+}
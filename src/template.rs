@@ -0,0 +1,212 @@
+// A small mustache-style template renderer for generated documents.
+//
+//   {{name}}             value of `name`, passed through the escape function
+//   {{name:<12}}         left-aligned in 12 columns ({{name:>12}} right-aligns)
+//   {{#list}}..{{/list}} body once per item of `list` (or once if `list` is
+//                        non-empty text); item values shadow outer ones
+//   {{^list}}..{{/list}} body only when `list` is missing or empty
+//
+// A newline directly after a section tag is dropped so sections can sit on
+// lines of their own. Unknown names render as nothing.
+
+use std::collections::HashMap;
+
+pub enum Value {
+    Text(String),
+    List(Vec<Context>),
+}
+
+#[derive(Default)]
+pub struct Context {
+    values: HashMap<String, Value>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Context::default()
+    }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) -> &mut Self {
+        self.values
+            .insert(key.to_string(), Value::Text(value.to_string()));
+        self
+    }
+
+    pub fn list(&mut self, key: &str, items: Vec<Context>) -> &mut Self {
+        self.values.insert(key.to_string(), Value::List(items));
+        self
+    }
+}
+
+pub fn render(template: &str, context: &Context, escape: fn(&str) -> String) -> String {
+    render_scoped(template, &[context], escape)
+}
+
+pub fn escape_html(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+pub fn plain(value: &str) -> String {
+    value.to_string()
+}
+
+fn lookup<'a>(scopes: &[&'a Context], name: &str) -> Option<&'a Value> {
+    scopes.iter().rev().find_map(|scope| scope.values.get(name))
+}
+
+fn render_scoped(template: &str, scopes: &[&Context], escape: fn(&str) -> String) -> String {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(open) = rest.find("{{") {
+        out.push_str(&rest[..open]);
+        let after = &rest[open + 2..];
+        let Some(close) = after.find("}}") else {
+            out.push_str(&rest[open..]);
+            return out;
+        };
+        let tag = after[..close].trim();
+        rest = &after[close + 2..];
+
+        let section = tag
+            .strip_prefix('#')
+            .map(|name| (name, false))
+            .or_else(|| tag.strip_prefix('^').map(|name| (name, true)));
+        if let Some((name, inverted)) = section {
+            rest = rest.strip_prefix('\n').unwrap_or(rest);
+            let end_tag = format!("{{{{/{}}}}}", name);
+            let (body, remainder) = match rest.find(&end_tag) {
+                Some(end) => (&rest[..end], &rest[end + end_tag.len()..]),
+                None => (rest, ""),
+            };
+            rest = remainder.strip_prefix('\n').unwrap_or(remainder);
+
+            let value = lookup(scopes, name);
+            let empty = match value {
+                None => true,
+                Some(Value::Text(text)) => text.is_empty(),
+                Some(Value::List(items)) => items.is_empty(),
+            };
+            if inverted {
+                if empty {
+                    out.push_str(&render_scoped(body, scopes, escape));
+                }
+            } else if let Some(Value::List(items)) = value {
+                for item in items {
+                    let mut inner = scopes.to_vec();
+                    inner.push(item);
+                    out.push_str(&render_scoped(body, &inner, escape));
+                }
+            } else if !empty {
+                out.push_str(&render_scoped(body, scopes, escape));
+            }
+            continue;
+        }
+
+        let (name, spec) = tag.split_once(':').unwrap_or((tag, ""));
+        let text = match lookup(scopes, name.trim()) {
+            Some(Value::Text(text)) => text.as_str(),
+            _ => "",
+        };
+        out.push_str(&escape(&pad(text, spec.trim())));
+    }
+    out.push_str(rest);
+    out
+}
+
+fn pad(text: &str, spec: &str) -> String {
+    let width = |digits: &str| digits.parse::<usize>().unwrap_or(0);
+    if let Some(digits) = spec.strip_prefix('<') {
+        format!("{:<w$}", text, w = width(digits))
+    } else if let Some(digits) = spec.strip_prefix('>') {
+        format!("{:>w$}", text, w = width(digits))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(name: &str) -> Context {
+        let mut context = Context::new();
+        context.set("name", name);
+        context
+    }
+
+    #[test]
+    fn values_and_padding() {
+        let mut context = Context::new();
+        context.set("name", "Tea").set("amount", 12.5);
+        assert_eq!(
+            render("{{ name }} costs {{amount}}", &context, plain),
+            "Tea costs 12.5"
+        );
+        assert_eq!(
+            render("[{{name:<6}}][{{name:>6}}]", &context, plain),
+            "[Tea   ][   Tea]"
+        );
+        assert_eq!(render("[{{missing}}]", &context, plain), "[]");
+        assert_eq!(render("{{name} open", &context, plain), "{{name} open");
+    }
+
+    #[test]
+    fn escaping_applies_to_values_only() {
+        let mut context = Context::new();
+        context.set("narration", "Fish & <chips> \"to go\"");
+        assert_eq!(
+            render("<td>{{narration}}</td>", &context, escape_html),
+            "<td>Fish &amp; &lt;chips&gt; &quot;to go&quot;</td>"
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let mut context = Context::new();
+        context
+            .set("title", "Food")
+            .set("note", "")
+            .list("rows", vec![item("Tea"), item("Lunch")])
+            .list("none", Vec::new());
+
+        let rows = "{{#rows}}\n- {{name}} ({{title}})\n{{/rows}}\nend";
+        assert_eq!(
+            render(rows, &context, plain),
+            "- Tea (Food)\n- Lunch (Food)\nend"
+        );
+        assert_eq!(
+            render("{{^none}}\nNo rows\n{{/none}}\n", &context, plain),
+            "No rows\n"
+        );
+        assert_eq!(
+            render("{{#title}}has {{title}}{{/title}}", &context, plain),
+            "has Food"
+        );
+        assert_eq!(
+            render(
+                "{{#note}}note{{/note}}{{^note}}no note{{/note}}",
+                &context,
+                plain
+            ),
+            "no note"
+        );
+        assert_eq!(render("{{#missing}}x{{/missing}}", &context, plain), "");
+    }
+
+    #[test]
+    fn items_shadow_outer_values() {
+        let mut context = Context::new();
+        context
+            .set("name", "outer")
+            .list("rows", vec![item("inner")]);
+        assert_eq!(
+            render("{{#rows}}{{name}}{{/rows}} {{name}}", &context, plain),
+            "inner outer"
+        );
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs::File;
+use std::io::{self, Write};
+
+use chrono::Local;
+use postgres::GenericClient;
+
+use crate::{WalletDB, WalletError};
+
+// Objects that only make sense once proceedings are on the v2 schema. Shared
+// by `upgrade` and `db-setup` on fresh databases.
+pub const V2_OBJECTS: &str = "
+    CREATE OR REPLACE FUNCTION proceedings_effective_date() RETURNS TRIGGER AS $$
+    BEGIN
+        NEW.effective_date := COALESCE(NEW.effective_date, NEW.created_at::date, CURRENT_DATE);
+        RETURN NEW;
+    END
+    $$ LANGUAGE plpgsql;
+
+    DROP TRIGGER IF EXISTS proceedings_effective_date ON proceedings;
+    CREATE TRIGGER proceedings_effective_date
+        BEFORE INSERT ON proceedings
+        FOR EACH ROW EXECUTE FUNCTION proceedings_effective_date();
+
+    CREATE INDEX IF NOT EXISTS idx_proceedings_effective_date ON proceedings (effective_date);
+";
+
+// Rounding differences listed individually before the report switches to a count
+const ROUNDING_SHOWN: usize = 20;
+
+impl WalletDB {
+    // 1 for the original DOUBLE PRECISION amounts, 2 once they are NUMERIC
+    pub(crate) fn schema_version(&mut self) -> Result<u8, WalletError> {
+        let row = self.client.query_opt(
+            "SELECT data_type::text FROM information_schema.columns
+             WHERE table_schema = current_schema()
+               AND table_name = 'proceedings' AND column_name = 'amount'",
+            &[],
+        )?;
+        match row.map(|row| row.get::<_, String>(0)) {
+            Some(data_type) if data_type == "numeric" => Ok(2),
+            Some(_) => Ok(1),
+            None => Err(WalletError::Upgrade(
+                "proceedings table not found; run db-setup first".to_string(),
+            )),
+        }
+    }
+
+    // Guided v1 -> v2 upgrade: backs proceedings up, converts amounts to
+    // NUMERIC(14,2) with a report of every value that had to be rounded,
+    // backfills uuid/effective_date, rebuilds indexes and checks that each
+    // ledger's balance moved by no more than `tolerance`. Everything after the
+    // CSV backup runs in one transaction, so a failed check changes nothing;
+    // a dry run rolls it back and skips the CSV.
+    pub(crate) fn upgrade(
+        &mut self,
+        backup: Option<&str>,
+        currency: &str,
+        tolerance: f64,
+        dry_run: bool,
+    ) -> Result<(), WalletError> {
+        let currency = currency.to_uppercase();
+        if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(WalletError::Upgrade(format!(
+                "'{}' is not a three-letter currency code",
+                currency
+            )));
+        }
+        if self.schema_version()? == 2 {
+            outln!("Database is already on the v2 schema; nothing to do.");
+            return Ok(());
+        }
+
+        // Step 1: backup
+        let backup = backup.map(str::to_string).unwrap_or_else(|| {
+            format!(
+                "spendlog-v1-backup-{}.csv",
+                Local::now().format("%Y%m%d%H%M%S")
+            )
+        });
+        if dry_run {
+            outln!("Step 1/6: dry run, would back up proceedings to {}", backup);
+        } else {
+            outln!("Step 1/6: backing up proceedings to {}", backup);
+            let mut file = File::create(&backup)?;
+            let mut reader = self
+                .client
+                .copy_out("COPY proceedings TO STDOUT WITH (FORMAT csv, HEADER)")?;
+            io::copy(&mut reader, &mut file)?;
+            drop(reader);
+            file.flush()?;
+        }
+
+        let mut transaction = self.client.transaction()?;
+        transaction.batch_execute(
+            "DROP TABLE IF EXISTS proceedings_v1_backup;
+             CREATE TABLE proceedings_v1_backup AS TABLE proceedings;",
+        )?;
+        let before = ledger_totals(&mut transaction)?;
+
+        // Step 2: rounding report
+        outln!("Step 2/6: checking amounts that do not fit two decimal places");
+        let rounded = transaction.query(
+            "SELECT id, amount, ROUND(amount::numeric, 2)::float8
+             FROM proceedings
+             WHERE amount::numeric <> ROUND(amount::numeric, 2)
+             ORDER BY id",
+            &[],
+        )?;
+        if rounded.is_empty() {
+            outln!("  No amounts need rounding.");
+        } else {
+            outln!("  {:<10} {:<20} {:<15}", "Id", "Stored", "Rounded");
+            let mut drift = 0.0;
+            for (i, row) in rounded.iter().enumerate() {
+                let (id, stored, kept): (i32, f64, f64) = (row.get(0), row.get(1), row.get(2));
+                drift += kept - stored;
+                if i < ROUNDING_SHOWN {
+                    outln!("  {:<10} {:<20} {:<15.2}", id, stored, kept);
+                }
+            }
+            if rounded.len() > ROUNDING_SHOWN {
+                outln!("  ... and {} more", rounded.len() - ROUNDING_SHOWN);
+            }
+            outln!(
+                "  {} amounts rounded, net change {:.6}",
+                rounded.len(),
+                drift
+            );
+        }
+
+        // Step 3: convert amounts and add the v2 columns
+        outln!("Step 3/6: converting amounts to NUMERIC(14,2)");
+        transaction.batch_execute(&format!(
+            "ALTER TABLE proceedings
+                 ALTER COLUMN amount TYPE NUMERIC(14, 2) USING ROUND(amount::numeric, 2),
+                 ADD COLUMN currency CHAR(3) NOT NULL DEFAULT '{}',
+                 ADD COLUMN uuid UUID,
+                 ADD COLUMN effective_date DATE;",
+            currency
+        ))?;
+
+        // Step 4: backfill
+        outln!("Step 4/6: backfilling uuid and effective_date");
+        let backfilled = transaction.execute(
+            "UPDATE proceedings
+             SET uuid = gen_random_uuid(),
+                 effective_date = COALESCE(created_at::date, CURRENT_DATE)",
+            &[],
+        )?;
+        transaction.batch_execute(
+            "ALTER TABLE proceedings
+                 ALTER COLUMN uuid SET DEFAULT gen_random_uuid(),
+                 ALTER COLUMN uuid SET NOT NULL,
+                 ALTER COLUMN effective_date SET NOT NULL,
+                 ADD CONSTRAINT proceedings_uuid_key UNIQUE (uuid);",
+        )?;
+        outln!("  {} proceedings backfilled", backfilled);
+
+        // Step 5: indexes
+        outln!("Step 5/6: rebuilding indexes");
+        transaction.batch_execute(V2_OBJECTS)?;
+        transaction.batch_execute("REINDEX TABLE proceedings;")?;
+
+        // Step 6: verify
+        outln!(
+            "Step 6/6: verifying ledger totals (tolerance {})",
+            tolerance
+        );
+        let after = ledger_totals(&mut transaction)?;
+        let mut drifted = 0;
+        outln!(
+            "  {:<10} {:<15} {:<15} {:<15}",
+            "Code",
+            "Before",
+            "After",
+            "Difference"
+        );
+        for ((code, old), (_, new)) in before.iter().zip(after.iter()) {
+            let difference = new - old;
+            let status = if difference.abs() > tolerance {
+                drifted += 1;
+                "  <- exceeds tolerance"
+            } else {
+                ""
+            };
+            outln!(
+                "  {:<10} {:<15.2} {:<15.2} {:<15.6}{}",
+                code,
+                old,
+                new,
+                difference,
+                status
+            );
+        }
+        if drifted > 0 {
+            transaction.rollback()?;
+            return Err(WalletError::Upgrade(format!(
+                "{} ledger total(s) drifted by more than {}; the database was left unchanged (backup: {})",
+                drifted, tolerance, backup
+            )));
+        }
+
+        if dry_run {
+            transaction.rollback()?;
+            outln!("Dry run complete; all changes rolled back and no backup written.");
+        } else {
+            transaction.commit()?;
+            outln!(
+                "Upgrade complete. The original rows are kept in proceedings_v1_backup and {}.",
+                backup
+            );
+        }
+        Ok(())
+    }
+}
+
+// Net balance (debits - credits) of every ledger, in code order
+fn ledger_totals(client: &mut impl GenericClient) -> Result<Vec<(String, f64)>, WalletError> {
+    let rows = client.query(
+        "
+        SELECT l.code,
+               COALESCE(SUM(CASE WHEN p.db_to = l.id THEN p.amount ELSE 0 END), 0)::float8 -
+               COALESCE(SUM(CASE WHEN p.cr_from = l.id THEN p.amount ELSE 0 END), 0)::float8
+        FROM ledgers l
+        LEFT JOIN proceedings p ON p.db_to = l.id OR p.cr_from = l.id
+        GROUP BY l.code
+        ORDER BY l.code
+        ",
+        &[],
+    )?;
+    Ok(rows.iter().map(|row| (row.get(0), row.get(1))).collect())
+}